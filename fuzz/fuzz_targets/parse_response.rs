@@ -0,0 +1,20 @@
+#![no_main]
+
+use eansearch::{Operation, PageRequest, Response, Symbology};
+use libfuzzer_sys::fuzz_target;
+
+// parsing any response body must return Ok or Err, but never panic
+fuzz_target!(|body: &str| {
+    let ops = [
+        Operation::BarcodeLookup { ean: 5099750442227, language: None },
+        Operation::IsbnLookup { isbn: 1119578884 },
+        Operation::ProductSearch { name: String::from("bananaboat"), language: None, page: PageRequest::default() },
+        Operation::IssuingCountry { ean: 5099750442227 },
+        Operation::VerifyChecksum { ean: 5099750442227 },
+        Operation::BarcodeImage { ean: 5099750442227, symbology: Symbology::Ean13, width: None, height: None },
+        Operation::AccountStatus,
+    ];
+    for op in &ops {
+        let _ = Response::parse(op, body);
+    }
+});
@@ -0,0 +1,223 @@
+//! Sequential allocation of EAN-13 codes under a GS1 company prefix
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::{Allocation, Ean};
+
+/// Keeps the last allocated item reference, so allocation continues after a restart
+pub trait AllocatorStore {
+    /// The last item reference allocated under the company prefix (None if there is none yet)
+    fn load(&mut self, company_prefix: &str) -> io::Result<Option<u64>>;
+    /// Record an allocated item reference, called before the code is handed out
+    fn save(&mut self, company_prefix: &str, item_reference: u64) -> io::Result<()>;
+}
+
+/// A store that keeps nothing: allocation starts again at item reference 0
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoStore;
+
+impl AllocatorStore for NoStore {
+    fn load(&mut self, _company_prefix: &str) -> io::Result<Option<u64>> {
+        Ok(None)
+    }
+
+    fn save(&mut self, _company_prefix: &str, _item_reference: u64) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A store that keeps the last item reference of each company prefix in a text file
+///
+/// Each line has a company prefix and its last item reference, eg. `4006381 41`. A file with only
+/// a number (written by earlier versions) belongs to the first prefix that allocates a code with it.
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    /// Use this file (created on the first allocation)
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The prefixes and their last item references (an empty prefix for a file with only a number)
+    fn entries(&self) -> io::Result<Vec<(String, u64)>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid allocator state {:?}", line));
+        let mut entries = Vec::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let fields : Vec<&str> = line.split_whitespace().collect();
+            let (prefix, item_reference) = match fields[..] {
+                [item_reference] => ("", item_reference),
+                [prefix, item_reference] => (prefix, item_reference),
+                _ => return Err(invalid(line)),
+            };
+            entries.push((prefix.to_string(), item_reference.parse().map_err(|_e| invalid(line))?));
+        }
+        Ok(entries)
+    }
+}
+
+impl AllocatorStore for FileStore {
+    fn load(&mut self, company_prefix: &str) -> io::Result<Option<u64>> {
+        let entries = self.entries()?;
+        Ok(entries.iter().find(|(prefix, _)| prefix == company_prefix || prefix.is_empty()).map(|(_, last)| *last))
+    }
+
+    fn save(&mut self, company_prefix: &str, item_reference: u64) -> io::Result<()> {
+        let mut entries = self.entries()?;
+        entries.retain(|(prefix, _)| prefix != company_prefix && !prefix.is_empty());
+        entries.push((company_prefix.to_string(), item_reference));
+        let text : String = entries.iter().map(|(prefix, last)| format!("{} {}\n", prefix, last)).collect();
+        // write and rename, so a crash can't leave a truncated file
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Error of an [`EanAllocator`]
+#[derive(Debug)]
+pub enum AllocationError {
+    /// The company prefix isn't a plausible GS1 company prefix
+    InvalidCompanyPrefix(String),
+    /// The store couldn't load or save the last item reference
+    Store(io::Error),
+}
+
+impl fmt::Display for AllocationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AllocationError::InvalidCompanyPrefix(prefix) => write!(f, "invalid GS1 company prefix {:?}", prefix),
+            AllocationError::Store(e) => write!(f, "allocator store: {}", e),
+        }
+    }
+}
+
+impl Error for AllocationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AllocationError::Store(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Hands out consecutive EAN-13 codes under a GS1 company prefix
+///
+/// The iterator ends when all item references of the prefix are used up.
+#[derive(Debug)]
+pub struct EanAllocator<S = NoStore> {
+    company_prefix: String,
+    next: u64,
+    capacity: u64,
+    store: S,
+}
+
+impl EanAllocator {
+    /// Start allocating at item reference 0 (without persistence)
+    ///
+    /// The company prefix has 6 to 12 digits (7 to 12 for US/Canada prefixes starting with 0).
+    pub fn new(company_prefix: &str) -> Result<Self, AllocationError> {
+        Self::with_store(company_prefix, NoStore)
+    }
+}
+
+impl<S: AllocatorStore> EanAllocator<S> {
+    /// Continue after the last item reference recorded in the store
+    pub fn with_store(company_prefix: &str, mut store: S) -> Result<Self, AllocationError> {
+        let invalid = || AllocationError::InvalidCompanyPrefix(company_prefix.to_string());
+        let min_len = if company_prefix.starts_with('0') { 7 } else { 6 };
+        if !(min_len..=12).contains(&company_prefix.len()) || !company_prefix.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let first = Ean::with_check_digit(&format!("{:0<12}", company_prefix)).map_err(|_e| invalid())?;
+        if !matches!(first.allocation(), Ok(Allocation::MemberOrganization(_)) | Ok(Allocation::GlobalOffice)) {
+            return Err(invalid());
+        }
+        let capacity = 10u64.pow(12 - company_prefix.len() as u32);
+        let next = match store.load(company_prefix).map_err(AllocationError::Store)? {
+            Some(last) => last.saturating_add(1),
+            None => 0,
+        };
+        Ok(Self { company_prefix: company_prefix.to_string(), next, capacity, store })
+    }
+
+    /// Number of codes that can still be allocated
+    pub fn remaining(&self) -> u64 {
+        self.capacity.saturating_sub(self.next)
+    }
+}
+
+impl<S: AllocatorStore> Iterator for EanAllocator<S> {
+    type Item = Result<Ean, AllocationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.capacity {
+            return None;
+        }
+        let item_reference = self.next;
+        if let Err(e) = self.store.save(&self.company_prefix, item_reference) {
+            return Some(Err(AllocationError::Store(e)));
+        }
+        self.next += 1;
+        let payload = format!("{}{}", self.company_prefix, &format!("{:012}", item_reference)[self.company_prefix.len()..]);
+        Some(Ean::with_check_digit(&payload).map_err(|_e| AllocationError::InvalidCompanyPrefix(self.company_prefix.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocator() {
+        let codes : Vec<String> = EanAllocator::new("4006381").unwrap().take(3).map(|e| e.unwrap().to_string()).collect();
+        assert_eq!(codes, vec!["4006381000000", "4006381000017", "4006381000024"]);
+        let mut allocator = EanAllocator::new("400638133393").unwrap();
+        assert_eq!(allocator.remaining(), 1);
+        assert_eq!(allocator.next().unwrap().unwrap().to_string(), "4006381333931");
+        assert!(allocator.next().is_none());
+        for prefix in ["40063", "4006381333931", "400x381", "036000", "2001234", "9780306"] {
+            assert!(EanAllocator::new(prefix).is_err(), "{}", prefix);
+        }
+        assert!(EanAllocator::new("03600029").is_ok());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let path = std::env::temp_dir().join(format!("eansearch-allocator-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut allocator = EanAllocator::with_store("4006381", FileStore::new(&path)).unwrap();
+        allocator.next();
+        allocator.next();
+        // a restarted allocator continues after the last code handed out
+        let mut allocator = EanAllocator::with_store("4006381", FileStore::new(&path)).unwrap();
+        assert_eq!(allocator.next().unwrap().unwrap().to_string(), "4006381000024");
+        assert_eq!(allocator.remaining(), 99_997);
+        // a corrupted store is exhausted, not an overflow
+        fs::write(&path, u64::MAX.to_string()).unwrap();
+        let mut allocator = EanAllocator::with_store("4006381", FileStore::new(&path)).unwrap();
+        assert!(allocator.next().is_none());
+        // each prefix has its own counter
+        fs::remove_file(&path).unwrap();
+        EanAllocator::with_store("4006381", FileStore::new(&path)).unwrap().take(5).for_each(drop);
+        let mut allocator = EanAllocator::with_store("5012345", FileStore::new(&path)).unwrap();
+        assert_eq!(allocator.next().unwrap().unwrap().to_string(), "5012345000008");
+        let mut allocator = EanAllocator::with_store("4006381", FileStore::new(&path)).unwrap();
+        assert_eq!(allocator.next().unwrap().unwrap().to_string(), "4006381000055");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "5012345 0\n4006381 5\n");
+        fs::write(&path, "4006381 x").unwrap();
+        assert!(EanAllocator::with_store("4006381", FileStore::new(&path)).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,409 @@
+//! The async client (enabled with the `async` or `async-ureq` feature)
+
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+    parse, proto, CallContext, EANSearchError, Endpoint, ErrorKind, Language, LookupResult, Operation, PageRequest,
+    Product, Profile, Response,
+};
+use crate::transport::{AsyncTransport, DefaultAsyncTransport};
+#[cfg(feature = "image")]
+use crate::Symbology;
+
+type ErrorHandler = Box<dyn Fn(&EANSearchError, &CallContext) + Send + Sync>;
+
+/// The access object to make API requests to the EAN database from async code
+///
+/// It has the same lookup methods as [`EANSearch`](crate::EANSearch), as async functions.
+/// The requests are sent with an [`AsyncTransport`]: reqwest's async client (on tokio) with the `async` feature,
+/// ureq on a thread pool (on any runtime) with the `async-ureq` feature.
+pub struct AsyncEANSearch<T: AsyncTransport = DefaultAsyncTransport> {
+    transport: T,
+    endpoint: Endpoint,
+    language: Option<i8>,
+    error_handler: Option<ErrorHandler>,
+    strict: bool,
+    timeout: Option<Duration>,
+}
+
+impl AsyncEANSearch {
+    /// Construct the database access object with your API token
+    /// (sending the requests to the URL in `EANSEARCH_BASE_URL` if set, like [`EANSearch::new`](crate::EANSearch::new))
+    ///
+    /// # Panics
+    ///
+    /// If the HTTP client can't be created, eg. when the TLS backend fails to initialize.
+    /// Use [`try_new`](Self::try_new) to get an error instead.
+    pub fn new(token: &str) -> Self {
+        Self::try_new(token).unwrap_or_else(|e| panic!("can't create the AsyncEANSearch client: {}", e))
+    }
+
+    /// Construct the database access object with your API token, failing if the HTTP client can't be created
+    pub fn try_new(token: &str) -> Result<Self, EANSearchError> {
+        Self::with_url(&proto::default_url(), token)
+    }
+
+    fn with_url(url: &str, token: &str) -> Result<Self, EANSearchError> {
+        Ok(Self::from_parts(Endpoint::with_url(url, token), default_transport()?))
+    }
+
+    /// Construct the database access object from a named profile in the config file or environment,
+    /// see [`Profile::load`]
+    pub fn profile(name: &str) -> Result<Self, EANSearchError> {
+        Self::build_from_profile(Profile::load(name)?)
+    }
+
+    /// Construct the database access object with the token, base URL and language of a profile
+    /// (the credit limits are only supported by the blocking client)
+    ///
+    /// # Panics
+    ///
+    /// If the HTTP client can't be created, like [`new`](Self::new).
+    pub fn from_profile(profile: Profile) -> Self {
+        Self::build_from_profile(profile).unwrap_or_else(|e| panic!("can't create the AsyncEANSearch client: {}", e))
+    }
+
+    fn build_from_profile(profile: Profile) -> Result<Self, EANSearchError> {
+        let mut eansearch = Self::with_url(&profile.base_url.unwrap_or_else(proto::default_url), &profile.token)?;
+        eansearch.language = profile.language;
+        Ok(eansearch)
+    }
+}
+
+/// The default transport, with an error instead of a panic if reqwest's client can't be created
+#[cfg(feature = "async")]
+fn default_transport() -> Result<DefaultAsyncTransport, ErrorKind> {
+    Ok(crate::AsyncReqwestTransport::new(reqwest::Client::builder().build()?))
+}
+
+#[cfg(not(feature = "async"))]
+fn default_transport() -> Result<DefaultAsyncTransport, ErrorKind> {
+    Ok(DefaultAsyncTransport::default())
+}
+
+impl<T: AsyncTransport> AsyncEANSearch<T> {
+    /// Construct the database access object with your API token and another transport, eg. a `HyperTransport`
+    /// (sending the requests to the URL in `EANSEARCH_BASE_URL` if set, like [`new`](AsyncEANSearch::new))
+    pub fn with_transport(token: &str, transport: T) -> Self {
+        Self::from_parts(Endpoint::with_url(&proto::default_url(), token), transport)
+    }
+
+    fn from_parts(endpoint: Endpoint, transport: T) -> Self {
+        Self { transport, endpoint, language: None, error_handler: None, strict: false, timeout: None }
+    }
+
+    /// The transport sending the requests
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Send the requests to another endpoint, eg. a mock server in tests
+    pub fn with_endpoint(mut self, endpoint: Endpoint) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// Fail API calls that take longer than `timeout`, see [`EANSearch::with_timeout`](crate::EANSearch::with_timeout)
+    /// (not on wasm32, where reqwest uses fetch)
+    ///
+    /// To give up on a call earlier, eg. when the client went away, drop its future:
+    /// the request is cancelled (with reqwest and hyper; ureq with `async-ureq` finishes the request on its thread).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Register a handler that is called with every error returned by an API method
+    pub fn on_error<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&EANSearchError, &CallContext) + Send + Sync + 'static,
+    {
+        self.error_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Fail on fields in the responses the parsers don't know, see [`EANSearch::strict`](crate::EANSearch::strict)
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Run an API operation, eg. one built programmatically
+    pub async fn execute(&self, op: &Operation) -> Result<Response, EANSearchError> {
+        self.call(op, parse::response).await
+    }
+
+    /// Call the API, parse the response and report errors to the error handler
+    async fn call<R>(&self, op: &Operation, parse: impl FnOnce(&Operation, &str) -> Result<R, ErrorKind>) -> Result<R, EANSearchError> {
+        let with_language = self.language.and_then(|language| op.with_default_language(language));
+        let op = with_language.as_ref().unwrap_or(op);
+        let mut request = self.endpoint.request(op);
+        request.timeout = self.timeout;
+        let (result, response) = match self.transport.send(&request).await {
+            Ok(response) => (proto::read_response(op, &response, self.strict, parse), Some(response)),
+            Err(kind) => (Err(kind), None),
+        };
+        result.map_err(|kind| {
+            let context = CallContext::new(op, request, response.as_ref());
+            let e = EANSearchError::with_context(kind, context);
+            if let (Some(handler), Some(context)) = (&self.error_handler, e.context()) {
+                handler(&e, context);
+            }
+            e
+        })
+    }
+
+    /// Search for a product by EAN barcode
+    pub async fn barcode_lookup(&self, ean: u64, language: Option<i8>) -> Result<Option<Product>, EANSearchError> {
+        self.call(&Operation::BarcodeLookup { ean, language }, parse::product).await
+    }
+
+    /// Search for several products by EAN barcode, one after the other, see [`EANSearch::lookup_many`](crate::EANSearch::lookup_many)
+    pub async fn lookup_many(&self, eans: &[u64], language: Option<i8>) -> Vec<(u64, LookupResult)> {
+        let mut results = Vec::with_capacity(eans.len());
+        for &ean in eans {
+            results.push((ean, self.barcode_lookup(ean, language).await));
+        }
+        results
+    }
+
+    /// Search for several products by EAN barcode with up to `concurrency` requests at a time,
+    /// yielding the results as they arrive (not in the order of `eans`)
+    ///
+    /// A concurrency of 0 is treated as 1.
+    pub fn lookup_many_concurrent<'a>(&'a self, eans: &'a [u64], language: Option<i8>, concurrency: usize) -> impl Stream<Item = (u64, LookupResult)> + 'a {
+        futures_util::stream::iter(eans)
+            .map(move |&ean| async move { (ean, self.barcode_lookup(ean, language).await) })
+            .buffer_unordered(concurrency.max(1))
+    }
+
+    /// Lookup a book by ISBN-10 or ISBN-13 code
+    pub async fn isbn_lookup(&self, isbn: u64) -> Result<Option<Product>, EANSearchError> {
+        self.call(&Operation::IsbnLookup { isbn }, parse::product).await
+    }
+
+    /// Search for all products with an EAN barcode staring with this prefix
+    pub async fn barcode_prefix_search(&self, prefix: u64, language: Option<i8>, page: PageRequest) -> Result<Vec<Product>, EANSearchError> {
+        self.call(&Operation::BarcodePrefixSearch { prefix, language, page }, parse::product_list).await
+    }
+
+    /// Search for all products matching all keywords in name parameter
+    pub async fn product_search(&self, name: &str, language: Option<i8>, page: PageRequest) -> Result<Vec<Product>, EANSearchError> {
+        self.call(&Operation::ProductSearch { name: name.to_string(), language, page }, parse::product_list).await
+    }
+
+    /// Stream all products with an EAN barcode starting with this prefix, fetching the pages as needed
+    pub fn barcode_prefix_search_stream(&self, prefix: u64, language: Option<i8>) -> impl Stream<Item = Result<Product, EANSearchError>> + '_ {
+        self.stream_products(Operation::BarcodePrefixSearch { prefix, language, page: PageRequest::default() })
+    }
+
+    /// Stream all products matching all keywords in name, fetching the pages as needed
+    pub fn product_search_stream(&self, name: &str, language: Option<i8>) -> impl Stream<Item = Result<Product, EANSearchError>> + '_ {
+        self.stream_products(Operation::ProductSearch { name: name.to_string(), language, page: PageRequest::default() })
+    }
+
+    /// Stream the products of a list operation (prefix, product or category search),
+    /// starting at the page of the operation and fetching further pages as the stream is polled
+    ///
+    /// Like [`EANSearch::iter_products`](crate::EANSearch::iter_products), the stream ends after the last page
+    /// (as the API reports it, or with the first empty page) or after the first error.
+    pub fn stream_products(&self, op: Operation) -> impl Stream<Item = Result<Product, EANSearchError>> + '_ {
+        let state = (Some(op), Vec::new().into_iter());
+        futures_util::stream::unfold(state, move |(mut next_op, mut products)| async move {
+            loop {
+                if let Some(product) = products.next() {
+                    return Some((Ok(product), (next_op, products)));
+                }
+                let op = next_op.take()?;
+                if op.page().is_none() {
+                    let msg = format!("{} is not a list operation", op.name());
+                    return Some((Err(ErrorKind::InvalidArgument(msg).into()), (None, products)));
+                }
+                match self.call(&op, parse::product_page).await {
+                    Ok((page, _)) if page.is_empty() => return None,
+                    Ok((page, more)) => {
+                        next_op = if more == Some(false) { None } else { op.next_page() };
+                        products = page.into_iter();
+                    }
+                    Err(e) => return Some((Err(e), (None, products))),
+                }
+            }
+        })
+    }
+
+    /// Search for all products in a product category, optionally restricted by keywords in name
+    pub async fn category_search(&self, category: i32, name: Option<&str>, language: Option<i8>, page: PageRequest) -> Result<Vec<Product>, EANSearchError> {
+        let name = name.map(String::from);
+        self.call(&Operation::CategorySearch { category, name, language, page }, parse::product_list).await
+    }
+
+    /// Query the country that issued an EAN barcode
+    pub async fn issuing_country(&self, ean: u64) -> Result<String, EANSearchError> {
+        self.call(&Operation::IssuingCountry { ean }, parse::issuing_country).await
+    }
+
+    /// Query the country that issued an EAN barcode as display name in a language
+    pub async fn issuing_country_name(&self, ean: u64, language: Language) -> Result<String, EANSearchError> {
+        let code = self.issuing_country(ean).await?;
+        Ok(crate::country_name(&code, language).map_or(code, String::from))
+    }
+
+    /// Verify if the provided number is a valid EAN barcode
+    pub async fn verify_checksum(&self, ean: u64) -> Result<bool, EANSearchError> {
+        self.call(&Operation::VerifyChecksum { ean }, parse::verify_checksum).await
+    }
+
+    /// Check how many requests are still available for your account in this payment cycle
+    pub async fn account_status(&self) -> Result<u32, EANSearchError> {
+        self.call(&Operation::AccountStatus, parse::account_status).await
+    }
+
+    /// Get a PNG image of the barcode, as EAN-8 for codes with up to 8 digits, otherwise as EAN-13
+    #[cfg(feature = "image")]
+    pub async fn barcode_image(&self, ean: u64, width: Option<i32>, height: Option<i32>) -> Result<Vec<u8>, EANSearchError> {
+        self.barcode_image_symbology(ean, Symbology::of(ean), width, height).await
+    }
+
+    /// Get a PNG image of the barcode in a symbology, see [`EANSearch::barcode_image_symbology`](crate::EANSearch::barcode_image_symbology)
+    #[cfg(feature = "image")]
+    pub async fn barcode_image_symbology(&self, ean: u64, symbology: Symbology, width: Option<i32>, height: Option<i32>) -> Result<Vec<u8>, EANSearchError> {
+        if !symbology.fits(ean) {
+            let msg = format!("{} has more than {} digits", ean, symbology.digits());
+            return Err(ErrorKind::InvalidArgument(msg).into());
+        }
+        self.call(&Operation::BarcodeImage { ean, symbology, width, height }, parse::barcode_image).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_error() {
+        // nothing listens on the discard port
+        let eansearch = AsyncEANSearch::new("secret").with_endpoint(Endpoint::with_url("http://127.0.0.1:9/api", "secret"));
+        let e = eansearch.barcode_lookup(5099750442227, None).await.unwrap_err();
+        #[cfg(feature = "async")]
+        assert!(matches!(e.kind(), ErrorKind::Http(_)));
+        #[cfg(not(feature = "async"))]
+        assert!(matches!(e.kind(), ErrorKind::Transport(_)));
+        assert!(e.is_retryable());
+        let context = e.context().unwrap();
+        assert_eq!(context.operation, "barcode-lookup");
+        assert_eq!(context.status, None);
+        assert!(!context.url.contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        // accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let eansearch = AsyncEANSearch::new("secret").with_endpoint(Endpoint::with_url(&url, "secret")).with_timeout(Duration::from_millis(100));
+        let start = std::time::Instant::now();
+        let e = eansearch.account_status().await.unwrap_err();
+        assert!(e.is_retryable());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_stream_products() {
+        use futures_util::StreamExt;
+        let eansearch = AsyncEANSearch::new("secret").with_endpoint(Endpoint::with_url("http://127.0.0.1:9/api", "secret"));
+        let mut stream = std::pin::pin!(eansearch.product_search_stream("bananaboat", None));
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+        let mut stream = std::pin::pin!(eansearch.stream_products(Operation::AccountStatus));
+        assert!(matches!(stream.next().await.unwrap().unwrap_err().kind(), ErrorKind::InvalidArgument(_)));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_many_concurrent() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use crate::{HttpRequest, HttpResponse};
+
+        /// Answers after yielding to the other lookups, counting the requests in flight
+        #[derive(Default)]
+        struct Slow {
+            in_flight: AtomicUsize,
+            max: AtomicUsize,
+        }
+
+        impl AsyncTransport for Slow {
+            async fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max.fetch_max(in_flight, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                let body = crate::fixtures::BARCODE_LOOKUP.as_bytes().to_vec();
+                Ok(HttpResponse { status: 200, headers: Vec::new(), body })
+            }
+        }
+
+        let eansearch = AsyncEANSearch::with_transport("secret", Slow::default());
+        let eans = [5099750442227, 4006381333931, 5012345678900, 96385074, 73513537];
+        let mut results : Vec<(u64, LookupResult)> = eansearch.lookup_many_concurrent(&eans, None, 2).collect().await;
+        results.sort_by_key(|(ean, _)| *ean);
+        let mut sorted = eans;
+        sorted.sort();
+        assert_eq!(results.iter().map(|(ean, _)| *ean).collect::<Vec<_>>(), sorted);
+        assert!(results.iter().all(|(_, result)| result.as_ref().unwrap().is_some()));
+        assert_eq!(eansearch.transport().max.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stream_last_page() {
+        use futures_util::StreamExt;
+        use crate::{HttpRequest, HttpResponse};
+
+        /// Answers every request with the same page
+        struct Canned;
+
+        impl AsyncTransport for Canned {
+            async fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+                let body = crate::fixtures::PRODUCT_SEARCH.as_bytes().to_vec();
+                Ok(HttpResponse { status: 200, headers: Vec::new(), body })
+            }
+        }
+
+        // moreproducts is false, so the stream ends without requesting another page
+        let eansearch = AsyncEANSearch::with_transport("secret", Canned);
+        assert_eq!(eansearch.product_search_stream("bananaboat", None).count().await, 2);
+    }
+
+    #[cfg(feature = "async-ureq")]
+    #[test]
+    fn test_unblock() {
+        // runs without a tokio runtime
+        let eansearch = AsyncEANSearch::with_transport("secret", crate::UnblockTransport::<crate::UreqTransport>::default())
+            .with_endpoint(Endpoint::with_url("http://127.0.0.1:9/api", "secret"));
+        let e = futures_executor::block_on(eansearch.account_status()).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Transport(_)));
+        assert!(e.is_retryable());
+    }
+
+    #[cfg(feature = "middleware")]
+    #[tokio::test]
+    async fn test_middleware() {
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new()).build();
+        let eansearch = AsyncEANSearch::with_transport("secret", crate::MiddlewareTransport::new(client))
+            .with_endpoint(Endpoint::with_url("http://127.0.0.1:9/api", "secret"));
+        let e = eansearch.account_status().await.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Http(_)));
+        assert!(e.is_retryable());
+    }
+
+    #[cfg(feature = "hyper")]
+    #[tokio::test]
+    async fn test_hyper() {
+        let eansearch = AsyncEANSearch::with_transport("secret", crate::HyperTransport::default())
+            .with_endpoint(Endpoint::with_url("http://127.0.0.1:9/api", "secret"));
+        // the futures can be spawned on a multi-threaded runtime
+        let e = tokio::spawn(async move { eansearch.account_status().await }).await.unwrap().unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Transport(_)));
+        assert!(e.is_retryable());
+    }
+}
@@ -0,0 +1,76 @@
+//! Books, periodicals and sheet music: the ISBN, ISSN or ISMN embedded in an EAN-13
+
+use crate::Ean;
+
+/// The publication identifier embedded in an EAN-13, see [`Ean::publication_id`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PublicationId {
+    /// A book (prefix 978 or 979): the ISBN-13 and, for prefix 978, the ISBN-10
+    Isbn { isbn13: String, isbn10: Option<String> },
+    /// A periodical (prefix 977): the ISSN, eg. "0317-8471"
+    Issn(String),
+    /// Sheet music (prefix 9790): the ISMN in its 10 character form, eg. "M230671187"
+    Ismn(String),
+}
+
+/// Check character of ISBN-10 and ISSN: weighted sum modulo 11, 10 is written as 'X'
+fn mod11_check(digits: &str) -> char {
+    let len = digits.len() as u32;
+    let sum : u32 = digits.bytes().enumerate().map(|(i, d)| u32::from(d - b'0') * (len + 1 - i as u32)).sum();
+    match (11 - sum % 11) % 11 {
+        10 => 'X',
+        check => char::from(b'0' + check as u8),
+    }
+}
+
+impl Ean {
+    /// The ISBN, ISSN or ISMN of a book, periodical or sheet music code (None for other codes)
+    pub fn publication_id(&self) -> Option<PublicationId> {
+        if self.digits() != 13 {
+            return None;
+        }
+        let digits = self.to_string();
+        if let Some(ismn) = digits.strip_prefix("9790") {
+            return Some(PublicationId::Ismn(format!("M{}", ismn)));
+        }
+        match &digits[..3] {
+            "977" => {
+                let issn = &digits[3..10];
+                Some(PublicationId::Issn(format!("{}-{}{}", &issn[..4], &issn[4..], mod11_check(issn))))
+            }
+            "978" => {
+                let isbn10 = format!("{}{}", &digits[3..12], mod11_check(&digits[3..12]));
+                Some(PublicationId::Isbn { isbn13: digits, isbn10: Some(isbn10) })
+            }
+            "979" => Some(PublicationId::Isbn { isbn13: digits, isbn10: None }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publication_id(code: &str) -> Option<PublicationId> {
+        Ean::parse(code).unwrap().publication_id()
+    }
+
+    #[test]
+    fn test_publication_id() {
+        assert_eq!(publication_id("9780306406157"), Some(PublicationId::Isbn {
+            isbn13: String::from("9780306406157"), isbn10: Some(String::from("0306406152")),
+        }));
+        assert_eq!(publication_id("9781119578888"), Some(PublicationId::Isbn {
+            isbn13: String::from("9781119578888"), isbn10: Some(String::from("1119578884")),
+        }));
+        assert_eq!(publication_id("9780804429573").unwrap(), PublicationId::Isbn {
+            isbn13: String::from("9780804429573"), isbn10: Some(String::from("080442957X")),
+        });
+        assert_eq!(publication_id("9791032305690"), Some(PublicationId::Isbn { isbn13: String::from("9791032305690"), isbn10: None }));
+        assert_eq!(publication_id("9770317847001"), Some(PublicationId::Issn(String::from("0317-8471"))));
+        assert_eq!(publication_id("9790230671187"), Some(PublicationId::Ismn(String::from("M230671187"))));
+        assert_eq!(publication_id("5099750442227"), None);
+        assert_eq!(publication_id("036000291452"), None);
+    }
+}
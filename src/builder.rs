@@ -0,0 +1,166 @@
+//! Building a client whose HTTP setup may fail
+
+use std::time::Duration;
+
+use crate::{EANSearch, EANSearchError, Endpoint, ErrorKind};
+use crate::transport::DefaultTransport;
+#[cfg(feature = "blocking")]
+use crate::ProxyAuth;
+
+/// The User-Agent header sent by the default transport, see [`EANSearchBuilder::user_agent_suffix`]
+pub const USER_AGENT: &str = concat!("eansearch-rust/", env!("CARGO_PKG_VERSION"));
+
+/// Builds an [`EANSearch`] client with the default transport, see [`EANSearch::builder`]
+///
+/// Unlike [`EANSearch::new`], [`build`](Self::build) returns an error if the HTTP client can't be created,
+/// eg. when the TLS backend fails to initialize or the proxy URL is invalid.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use eansearch::EANSearch;
+/// let eansearch = EANSearch::builder("token")
+///     .timeout(Duration::from_secs(10))
+///     .user_agent_suffix("inventory-sync/2.1")
+///     .build()?;
+/// # Ok::<(), eansearch::EANSearchError>(())
+/// ```
+pub struct EANSearchBuilder {
+    token: String,
+    base_url: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    #[cfg(feature = "blocking")]
+    proxy: Option<(String, Option<ProxyAuth>)>,
+    user_agent_suffix: Option<String>,
+}
+
+impl EANSearchBuilder {
+    pub(crate) fn new(token: &str) -> Self {
+        Self {
+            token: token.to_string(),
+            base_url: None,
+            timeout: None,
+            connect_timeout: None,
+            #[cfg(feature = "blocking")]
+            proxy: None,
+            user_agent_suffix: None,
+        }
+    }
+
+    /// Send the requests to another endpoint, eg. a mock server in tests or a regional endpoint
+    ///
+    /// Without it, the URL in [`BASE_URL_VAR`](crate::BASE_URL_VAR) (`EANSEARCH_BASE_URL`) is used if set,
+    /// otherwise [`API_URL`](crate::API_URL).
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = Some(url.to_string());
+        self
+    }
+
+    /// Fail API calls that take longer than `timeout` in total, see [`EANSearch::with_timeout`]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fail API calls that can't connect to the server within `timeout`
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Send the requests through an HTTP proxy with optional credentials, instead of the one in the environment
+    ///
+    /// Hosts in `NO_PROXY` are still reached directly. An invalid URL fails in [`build`](Self::build).
+    #[cfg(feature = "blocking")]
+    pub fn proxy(mut self, url: &str, auth: Option<ProxyAuth>) -> Self {
+        self.proxy = Some((url.to_string(), auth));
+        self
+    }
+
+    /// Identify your application in the User-Agent header, after the [`USER_AGENT`] of the crate,
+    /// eg. `inventory-sync/2.1`
+    pub fn user_agent_suffix(mut self, suffix: &str) -> Self {
+        self.user_agent_suffix = Some(suffix.to_string());
+        self
+    }
+
+    /// Create the HTTP client and the database access object
+    pub fn build(self) -> Result<EANSearch, EANSearchError> {
+        let url = self.base_url.clone().unwrap_or_else(crate::proto::default_url);
+        let mut eansearch = EANSearch::from_parts(Endpoint::with_url(&url, &self.token), self.transport()?);
+        eansearch.timeout = self.timeout;
+        Ok(eansearch)
+    }
+
+    fn user_agent(&self) -> String {
+        match &self.user_agent_suffix {
+            Some(suffix) => format!("{} {}", USER_AGENT, suffix),
+            None => USER_AGENT.to_string(),
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    fn transport(&self) -> Result<DefaultTransport, ErrorKind> {
+        let mut builder = reqwest::blocking::Client::builder().user_agent(self.user_agent());
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some((url, auth)) = &self.proxy {
+            builder = builder.proxy(crate::proxy::proxy(url, auth.as_ref())?);
+        }
+        Ok(crate::ReqwestTransport::new(builder.build()?))
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    fn transport(&self) -> Result<DefaultTransport, ErrorKind> {
+        let config = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .user_agent(self.user_agent())
+            .timeout_connect(self.connect_timeout)
+            .build();
+        Ok(crate::UreqTransport::new(config.into()))
+    }
+}
+
+impl std::fmt::Debug for EANSearchBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // don't leak the token into logs
+        let mut debug = f.debug_struct("EANSearchBuilder");
+        debug.field("base_url", &self.base_url).field("timeout", &self.timeout).field("connect_timeout", &self.connect_timeout);
+        #[cfg(feature = "blocking")]
+        debug.field("proxy", &self.proxy);
+        debug.field("user_agent", &self.user_agent()).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let builder = EANSearch::builder("secret");
+        assert!(!format!("{:?}", builder).contains("secret"));
+        let eansearch = builder.build().unwrap();
+        assert!(!format!("{:?}", eansearch.endpoint).contains("secret"));
+        assert_eq!(eansearch.timeout, None);
+
+        let builder = EANSearch::builder("secret").timeout(Duration::from_secs(5)).connect_timeout(Duration::from_secs(1))
+            .user_agent_suffix("inventory-sync/2.1");
+        assert_eq!(builder.user_agent(), format!("eansearch-rust/{} inventory-sync/2.1", env!("CARGO_PKG_VERSION")));
+        assert_eq!(builder.build().unwrap().timeout, Some(Duration::from_secs(5)));
+
+        let eansearch = EANSearch::builder("secret").base_url("http://localhost:8080/api").build().unwrap();
+        assert!(format!("{:?}", eansearch.endpoint).contains("http://localhost:8080/api?"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_builder_proxy() {
+        let auth = ProxyAuth::Basic { user: String::from("alice"), password: String::from("hunter2") };
+        let builder = EANSearch::builder("secret").proxy("http://proxy.example.com:3128", Some(auth));
+        assert!(!format!("{:?}", builder).contains("hunter2"));
+        assert!(builder.build().is_ok());
+        assert!(EANSearch::builder("secret").proxy("not a url", None).build().is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Classify a code string by barcode type, eg. to route scans
+
+use crate::Ean;
+
+/// The type of a barcode, see [`classify`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BarcodeType {
+    /// EAN-13 of a product
+    Ean13,
+    /// EAN-8 of a product
+    Ean8,
+    /// 12 digit UPC-A of a product
+    UpcA,
+    /// 8 digit UPC-E, the compressed form of a UPC-A code
+    UpcE,
+    /// Book or sheet music (EAN-13 with prefix 978 or 979)
+    Isbn13,
+    /// Periodical (EAN-13 with prefix 977)
+    Issn,
+    /// GTIN-14, eg. of a case of products
+    Gtin14,
+    /// Restricted circulation code for use within a store or company
+    Internal,
+    /// Not a valid barcode
+    Invalid,
+}
+
+/// Classify a code string (digits only, surrounding whitespace is ignored)
+///
+/// 8 digit codes starting with 0 or 1 that are valid UPC-E codes are classified as UPC-E,
+/// other 8 digit codes as EAN-8.
+pub fn classify(code: &str) -> BarcodeType {
+    classify_ean(code).0
+}
+
+/// The barcode type of a code, and the code itself if it is valid (expanded to UPC-A for UPC-E codes)
+pub(crate) fn classify_ean(code: &str) -> (BarcodeType, Option<Ean>) {
+    let code = code.trim();
+    if code.len() == 8 {
+        if let Ok(ean) = Ean::from_upce(code) {
+            return (BarcodeType::UpcE, Some(ean));
+        }
+    }
+    let ean = match Ean::parse(code) {
+        Ok(ean) => ean,
+        Err(_e) => return (BarcodeType::Invalid, None),
+    };
+    let barcode_type = if ean.is_restricted_circulation() {
+        BarcodeType::Internal
+    } else {
+        match (ean.digits(), ean.gs1_prefix()) {
+            (8, _) => BarcodeType::Ean8,
+            (12, _) => BarcodeType::UpcA,
+            (14, _) => BarcodeType::Gtin14,
+            (_, 977) => BarcodeType::Issn,
+            (_, 978..=979) => BarcodeType::Isbn13,
+            _ => BarcodeType::Ean13,
+        }
+    };
+    (barcode_type, Some(ean))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("5099750442227"), BarcodeType::Ean13);
+        assert_eq!(classify(" 96385074\n"), BarcodeType::Ean8);
+        assert_eq!(classify("036000291452"), BarcodeType::UpcA);
+        assert_eq!(classify("04252614"), BarcodeType::UpcE);
+        assert_eq!(classify("9780306406157"), BarcodeType::Isbn13);
+        assert_eq!(classify("9790230671187"), BarcodeType::Isbn13);
+        assert_eq!(classify("9770317847001"), BarcodeType::Issn);
+        assert_eq!(classify("15099750442224"), BarcodeType::Gtin14);
+        assert_eq!(classify("2812345012345"), BarcodeType::Internal);
+        assert_eq!(classify("20123451"), BarcodeType::Internal);
+        assert_eq!(classify("5099750442228"), BarcodeType::Invalid);
+        assert_eq!(classify("bananaboat"), BarcodeType::Invalid);
+        assert_eq!(classify(""), BarcodeType::Invalid);
+    }
+}
@@ -0,0 +1,155 @@
+//! Display names for the country codes returned by [`EANSearch::issuing_country`](crate::EANSearch::issuing_country)
+
+use crate::Language;
+
+// the languages of the name columns in COUNTRIES
+const COLUMNS: [&str; 6] = ["en", "de", "fr", "es", "it", "nl"];
+
+// GS1 member countries, with the codes the API returns (eg. "UK" for the United Kingdom)
+const COUNTRIES: &[(&str, [&str; 6])] = &[
+    ("AE", ["United Arab Emirates", "Vereinigte Arabische Emirate", "Émirats arabes unis", "Emiratos Árabes Unidos", "Emirati Arabi Uniti", "Verenigde Arabische Emiraten"]),
+    ("AL", ["Albania", "Albanien", "Albanie", "Albania", "Albania", "Albanië"]),
+    ("AM", ["Armenia", "Armenien", "Arménie", "Armenia", "Armenia", "Armenië"]),
+    ("AR", ["Argentina", "Argentinien", "Argentine", "Argentina", "Argentina", "Argentinië"]),
+    ("AT", ["Austria", "Österreich", "Autriche", "Austria", "Austria", "Oostenrijk"]),
+    ("AU", ["Australia", "Australien", "Australie", "Australia", "Australia", "Australië"]),
+    ("AZ", ["Azerbaijan", "Aserbaidschan", "Azerbaïdjan", "Azerbaiyán", "Azerbaigian", "Azerbeidzjan"]),
+    ("BA", ["Bosnia and Herzegovina", "Bosnien und Herzegowina", "Bosnie-Herzégovine", "Bosnia y Herzegovina", "Bosnia ed Erzegovina", "Bosnië en Herzegovina"]),
+    ("BE", ["Belgium", "Belgien", "Belgique", "Bélgica", "Belgio", "België"]),
+    ("BG", ["Bulgaria", "Bulgarien", "Bulgarie", "Bulgaria", "Bulgaria", "Bulgarije"]),
+    ("BH", ["Bahrain", "Bahrain", "Bahreïn", "Baréin", "Bahrein", "Bahrein"]),
+    ("BN", ["Brunei", "Brunei", "Brunei", "Brunéi", "Brunei", "Brunei"]),
+    ("BO", ["Bolivia", "Bolivien", "Bolivie", "Bolivia", "Bolivia", "Bolivia"]),
+    ("BR", ["Brazil", "Brasilien", "Brésil", "Brasil", "Brasile", "Brazilië"]),
+    ("BY", ["Belarus", "Belarus", "Biélorussie", "Bielorrusia", "Bielorussia", "Belarus"]),
+    ("CA", ["Canada", "Kanada", "Canada", "Canadá", "Canada", "Canada"]),
+    ("CH", ["Switzerland", "Schweiz", "Suisse", "Suiza", "Svizzera", "Zwitserland"]),
+    ("CI", ["Côte d'Ivoire", "Elfenbeinküste", "Côte d'Ivoire", "Costa de Marfil", "Costa d'Avorio", "Ivoorkust"]),
+    ("CL", ["Chile", "Chile", "Chili", "Chile", "Cile", "Chili"]),
+    ("CM", ["Cameroon", "Kamerun", "Cameroun", "Camerún", "Camerun", "Kameroen"]),
+    ("CN", ["China", "China", "Chine", "China", "Cina", "China"]),
+    ("CO", ["Colombia", "Kolumbien", "Colombie", "Colombia", "Colombia", "Colombia"]),
+    ("CR", ["Costa Rica", "Costa Rica", "Costa Rica", "Costa Rica", "Costa Rica", "Costa Rica"]),
+    ("CU", ["Cuba", "Kuba", "Cuba", "Cuba", "Cuba", "Cuba"]),
+    ("CY", ["Cyprus", "Zypern", "Chypre", "Chipre", "Cipro", "Cyprus"]),
+    ("CZ", ["Czechia", "Tschechien", "Tchéquie", "Chequia", "Cechia", "Tsjechië"]),
+    ("DE", ["Germany", "Deutschland", "Allemagne", "Alemania", "Germania", "Duitsland"]),
+    ("DK", ["Denmark", "Dänemark", "Danemark", "Dinamarca", "Danimarca", "Denemarken"]),
+    ("DO", ["Dominican Republic", "Dominikanische Republik", "République dominicaine", "República Dominicana", "Repubblica Dominicana", "Dominicaanse Republiek"]),
+    ("DZ", ["Algeria", "Algerien", "Algérie", "Argelia", "Algeria", "Algerije"]),
+    ("EC", ["Ecuador", "Ecuador", "Équateur", "Ecuador", "Ecuador", "Ecuador"]),
+    ("EE", ["Estonia", "Estland", "Estonie", "Estonia", "Estonia", "Estland"]),
+    ("EG", ["Egypt", "Ägypten", "Égypte", "Egipto", "Egitto", "Egypte"]),
+    ("ES", ["Spain", "Spanien", "Espagne", "España", "Spagna", "Spanje"]),
+    ("FI", ["Finland", "Finnland", "Finlande", "Finlandia", "Finlandia", "Finland"]),
+    ("FR", ["France", "Frankreich", "France", "Francia", "Francia", "Frankrijk"]),
+    ("GE", ["Georgia", "Georgien", "Géorgie", "Georgia", "Georgia", "Georgië"]),
+    ("GH", ["Ghana", "Ghana", "Ghana", "Ghana", "Ghana", "Ghana"]),
+    ("GR", ["Greece", "Griechenland", "Grèce", "Grecia", "Grecia", "Griekenland"]),
+    ("GT", ["Guatemala", "Guatemala", "Guatemala", "Guatemala", "Guatemala", "Guatemala"]),
+    ("HK", ["Hong Kong", "Hongkong", "Hong Kong", "Hong Kong", "Hong Kong", "Hongkong"]),
+    ("HN", ["Honduras", "Honduras", "Honduras", "Honduras", "Honduras", "Honduras"]),
+    ("HR", ["Croatia", "Kroatien", "Croatie", "Croacia", "Croazia", "Kroatië"]),
+    ("HU", ["Hungary", "Ungarn", "Hongrie", "Hungría", "Ungheria", "Hongarije"]),
+    ("ID", ["Indonesia", "Indonesien", "Indonésie", "Indonesia", "Indonesia", "Indonesië"]),
+    ("IE", ["Ireland", "Irland", "Irlande", "Irlanda", "Irlanda", "Ierland"]),
+    ("IL", ["Israel", "Israel", "Israël", "Israel", "Israele", "Israël"]),
+    ("IN", ["India", "Indien", "Inde", "India", "India", "India"]),
+    ("IR", ["Iran", "Iran", "Iran", "Irán", "Iran", "Iran"]),
+    ("IS", ["Iceland", "Island", "Islande", "Islandia", "Islanda", "IJsland"]),
+    ("IT", ["Italy", "Italien", "Italie", "Italia", "Italia", "Italië"]),
+    ("JO", ["Jordan", "Jordanien", "Jordanie", "Jordania", "Giordania", "Jordanië"]),
+    ("JP", ["Japan", "Japan", "Japon", "Japón", "Giappone", "Japan"]),
+    ("KE", ["Kenya", "Kenia", "Kenya", "Kenia", "Kenya", "Kenia"]),
+    ("KG", ["Kyrgyzstan", "Kirgisistan", "Kirghizistan", "Kirguistán", "Kirghizistan", "Kirgizië"]),
+    ("KH", ["Cambodia", "Kambodscha", "Cambodge", "Camboya", "Cambogia", "Cambodja"]),
+    ("KP", ["North Korea", "Nordkorea", "Corée du Nord", "Corea del Norte", "Corea del Nord", "Noord-Korea"]),
+    ("KR", ["South Korea", "Südkorea", "Corée du Sud", "Corea del Sur", "Corea del Sud", "Zuid-Korea"]),
+    ("KW", ["Kuwait", "Kuwait", "Koweït", "Kuwait", "Kuwait", "Koeweit"]),
+    ("KZ", ["Kazakhstan", "Kasachstan", "Kazakhstan", "Kazajistán", "Kazakistan", "Kazachstan"]),
+    ("LB", ["Lebanon", "Libanon", "Liban", "Líbano", "Libano", "Libanon"]),
+    ("LK", ["Sri Lanka", "Sri Lanka", "Sri Lanka", "Sri Lanka", "Sri Lanka", "Sri Lanka"]),
+    ("LT", ["Lithuania", "Litauen", "Lituanie", "Lituania", "Lituania", "Litouwen"]),
+    ("LU", ["Luxembourg", "Luxemburg", "Luxembourg", "Luxemburgo", "Lussemburgo", "Luxemburg"]),
+    ("LV", ["Latvia", "Lettland", "Lettonie", "Letonia", "Lettonia", "Letland"]),
+    ("LY", ["Libya", "Libyen", "Libye", "Libia", "Libia", "Libië"]),
+    ("MA", ["Morocco", "Marokko", "Maroc", "Marruecos", "Marocco", "Marokko"]),
+    ("MD", ["Moldova", "Moldau", "Moldavie", "Moldavia", "Moldavia", "Moldavië"]),
+    ("ME", ["Montenegro", "Montenegro", "Monténégro", "Montenegro", "Montenegro", "Montenegro"]),
+    ("MK", ["North Macedonia", "Nordmazedonien", "Macédoine du Nord", "Macedonia del Norte", "Macedonia del Nord", "Noord-Macedonië"]),
+    ("MM", ["Myanmar", "Myanmar", "Birmanie", "Birmania", "Birmania", "Myanmar"]),
+    ("MN", ["Mongolia", "Mongolei", "Mongolie", "Mongolia", "Mongolia", "Mongolië"]),
+    ("MO", ["Macao", "Macau", "Macao", "Macao", "Macao", "Macau"]),
+    ("MT", ["Malta", "Malta", "Malte", "Malta", "Malta", "Malta"]),
+    ("MU", ["Mauritius", "Mauritius", "Maurice", "Mauricio", "Mauritius", "Mauritius"]),
+    ("MX", ["Mexico", "Mexiko", "Mexique", "México", "Messico", "Mexico"]),
+    ("MY", ["Malaysia", "Malaysia", "Malaisie", "Malasia", "Malesia", "Maleisië"]),
+    ("NA", ["Namibia", "Namibia", "Namibie", "Namibia", "Namibia", "Namibië"]),
+    ("NG", ["Nigeria", "Nigeria", "Nigeria", "Nigeria", "Nigeria", "Nigeria"]),
+    ("NI", ["Nicaragua", "Nicaragua", "Nicaragua", "Nicaragua", "Nicaragua", "Nicaragua"]),
+    ("NL", ["Netherlands", "Niederlande", "Pays-Bas", "Países Bajos", "Paesi Bassi", "Nederland"]),
+    ("NO", ["Norway", "Norwegen", "Norvège", "Noruega", "Norvegia", "Noorwegen"]),
+    ("NZ", ["New Zealand", "Neuseeland", "Nouvelle-Zélande", "Nueva Zelanda", "Nuova Zelanda", "Nieuw-Zeeland"]),
+    ("PA", ["Panama", "Panama", "Panama", "Panamá", "Panama", "Panama"]),
+    ("PE", ["Peru", "Peru", "Pérou", "Perú", "Perù", "Peru"]),
+    ("PH", ["Philippines", "Philippinen", "Philippines", "Filipinas", "Filippine", "Filipijnen"]),
+    ("PK", ["Pakistan", "Pakistan", "Pakistan", "Pakistán", "Pakistan", "Pakistan"]),
+    ("PL", ["Poland", "Polen", "Pologne", "Polonia", "Polonia", "Polen"]),
+    ("PT", ["Portugal", "Portugal", "Portugal", "Portugal", "Portogallo", "Portugal"]),
+    ("PY", ["Paraguay", "Paraguay", "Paraguay", "Paraguay", "Paraguay", "Paraguay"]),
+    ("QA", ["Qatar", "Katar", "Qatar", "Catar", "Qatar", "Qatar"]),
+    ("RO", ["Romania", "Rumänien", "Roumanie", "Rumania", "Romania", "Roemenië"]),
+    ("RS", ["Serbia", "Serbien", "Serbie", "Serbia", "Serbia", "Servië"]),
+    ("RU", ["Russia", "Russland", "Russie", "Rusia", "Russia", "Rusland"]),
+    ("SA", ["Saudi Arabia", "Saudi-Arabien", "Arabie saoudite", "Arabia Saudí", "Arabia Saudita", "Saoedi-Arabië"]),
+    ("SE", ["Sweden", "Schweden", "Suède", "Suecia", "Svezia", "Zweden"]),
+    ("SG", ["Singapore", "Singapur", "Singapour", "Singapur", "Singapore", "Singapore"]),
+    ("SI", ["Slovenia", "Slowenien", "Slovénie", "Eslovenia", "Slovenia", "Slovenië"]),
+    ("SK", ["Slovakia", "Slowakei", "Slovaquie", "Eslovaquia", "Slovacchia", "Slowakije"]),
+    ("SN", ["Senegal", "Senegal", "Sénégal", "Senegal", "Senegal", "Senegal"]),
+    ("SV", ["El Salvador", "El Salvador", "Salvador", "El Salvador", "El Salvador", "El Salvador"]),
+    ("SY", ["Syria", "Syrien", "Syrie", "Siria", "Siria", "Syrië"]),
+    ("TH", ["Thailand", "Thailand", "Thaïlande", "Tailandia", "Thailandia", "Thailand"]),
+    ("TJ", ["Tajikistan", "Tadschikistan", "Tadjikistan", "Tayikistán", "Tagikistan", "Tadzjikistan"]),
+    ("TM", ["Turkmenistan", "Turkmenistan", "Turkménistan", "Turkmenistán", "Turkmenistan", "Turkmenistan"]),
+    ("TN", ["Tunisia", "Tunesien", "Tunisie", "Túnez", "Tunisia", "Tunesië"]),
+    ("TR", ["Turkey", "Türkei", "Turquie", "Turquía", "Turchia", "Turkije"]),
+    ("TW", ["Taiwan", "Taiwan", "Taïwan", "Taiwán", "Taiwan", "Taiwan"]),
+    ("TZ", ["Tanzania", "Tansania", "Tanzanie", "Tanzania", "Tanzania", "Tanzania"]),
+    ("UA", ["Ukraine", "Ukraine", "Ukraine", "Ucrania", "Ucraina", "Oekraïne"]),
+    ("UK", ["United Kingdom", "Vereinigtes Königreich", "Royaume-Uni", "Reino Unido", "Regno Unito", "Verenigd Koninkrijk"]),
+    ("US", ["United States", "Vereinigte Staaten", "États-Unis", "Estados Unidos", "Stati Uniti", "Verenigde Staten"]),
+    ("UY", ["Uruguay", "Uruguay", "Uruguay", "Uruguay", "Uruguay", "Uruguay"]),
+    ("UZ", ["Uzbekistan", "Usbekistan", "Ouzbékistan", "Uzbekistán", "Uzbekistan", "Oezbekistan"]),
+    ("VE", ["Venezuela", "Venezuela", "Venezuela", "Venezuela", "Venezuela", "Venezuela"]),
+    ("VN", ["Vietnam", "Vietnam", "Viêt Nam", "Vietnam", "Vietnam", "Vietnam"]),
+    ("XK", ["Kosovo", "Kosovo", "Kosovo", "Kosovo", "Kosovo", "Kosovo"]),
+    ("ZA", ["South Africa", "Südafrika", "Afrique du Sud", "Sudáfrica", "Sudafrica", "Zuid-Afrika"]),
+];
+
+/// The name of a country in a language, for a code returned by the API (eg. "UK")
+///
+/// Names are available in English, German, French, Spanish, Italian and Dutch,
+/// other languages get the English name. Returns None for unknown codes.
+pub fn country_name(code: &str, language: Language) -> Option<&'static str> {
+    let code = if code.eq_ignore_ascii_case("GB") { "UK" } else { code };
+    let (_, names) = COUNTRIES.iter().find(|(c, _)| c.eq_ignore_ascii_case(code))?;
+    let column = COLUMNS.iter().position(|iso| *iso == language.iso).unwrap_or(0);
+    Some(names[column])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_country_name() {
+        let german = Language::from_iso("de").unwrap();
+        assert_eq!(country_name("UK", german), Some("Vereinigtes Königreich"));
+        assert_eq!(country_name("gb", Language::from_iso("fr").unwrap()), Some("Royaume-Uni"));
+        assert_eq!(country_name("DE", Language::from_iso("sv").unwrap()), Some("Germany"));
+        assert_eq!(country_name("DE", Language::ANY), Some("Germany"));
+        assert_eq!(country_name("XX", german), None);
+        assert!(COUNTRIES.windows(2).all(|w| w[0].0 < w[1].0)); // sorted, no duplicates
+    }
+}
@@ -0,0 +1,111 @@
+//! Comparing two sets of products, eg. two downloads of a category
+
+use std::collections::HashMap;
+
+use crate::Product;
+
+/// The differences between two sets of products, matched by EAN, see [`diff`]
+#[derive(Clone, Debug, Default)]
+pub struct ProductDiff {
+    /// Products only in the new set (in the order of the new set)
+    pub added: Vec<Product>,
+    /// Products only in the old set (in the order of the old set)
+    pub removed: Vec<Product>,
+    /// Products in both sets with different fields (in the order of the new set)
+    pub modified: Vec<ProductChange>,
+}
+
+impl ProductDiff {
+    /// Whether both sets have the same products
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// The changed fields of a product
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProductChange {
+    pub ean: u64,
+    pub fields: Vec<FieldChange>,
+}
+
+/// The old and new value of a field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    /// The field name, eg. "name" or "category_id"
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// Compare two sets of products by EAN, eg. from two enrichment runs
+///
+/// If an EAN occurs several times in a set, the last occurrence is used.
+pub fn diff(old: &[Product], new: &[Product]) -> ProductDiff {
+    let old_by_ean : HashMap<u64, &Product> = old.iter().map(|p| (p.ean, p)).collect();
+    let new_by_ean : HashMap<u64, &Product> = new.iter().map(|p| (p.ean, p)).collect();
+    let mut result = ProductDiff::default();
+    for p in new {
+        // only report each EAN once, for its last occurrence
+        if !std::ptr::eq(new_by_ean[&p.ean], p) {
+            continue;
+        }
+        match old_by_ean.get(&p.ean) {
+            None => result.added.push(p.clone()),
+            Some(old) => {
+                let fields = changed_fields(old, p);
+                if !fields.is_empty() {
+                    result.modified.push(ProductChange { ean: p.ean, fields });
+                }
+            }
+        }
+    }
+    result.removed = old.iter()
+        .filter(|p| std::ptr::eq(old_by_ean[&p.ean], *p) && !new_by_ean.contains_key(&p.ean))
+        .cloned()
+        .collect();
+    result
+}
+
+fn changed_fields(old: &Product, new: &Product) -> Vec<FieldChange> {
+    let google_category = |p: &Product| p.google_category_id.map(|id| id.to_string()).unwrap_or_default();
+    let fields : [(&'static str, String, String); 5] = [
+        ("name", old.name.clone(), new.name.clone()),
+        ("category_id", old.category_id.to_string(), new.category_id.to_string()),
+        ("category_name", old.category_name.clone(), new.category_name.clone()),
+        ("google_category_id", google_category(old), google_category(new)),
+        ("issuing_country", old.issuing_country.clone(), new.issuing_country.clone()),
+    ];
+    fields.into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(field, old, new)| FieldChange { field, old, new })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_diff() {
+        let old = fixtures::product_list();
+        assert!(diff(&old, &old).is_empty());
+        let mut new = old.clone();
+        let removed = new.remove(0);
+        new[0].name = String::from("Thriller");
+        new.push(fixtures::product());
+        new[1].ean = 4006381333931;
+        let result = diff(&old, &new);
+        assert_eq!(result.removed.len(), 1);
+        assert_eq!(result.removed[0].ean, removed.ean);
+        assert_eq!(result.added.len(), 1);
+        assert_eq!(result.added[0].ean, 4006381333931);
+        assert_eq!(result.modified, vec![ProductChange {
+            ean: 5099750442227,
+            fields: vec![FieldChange { field: "name", old: String::from("Michael Jackson: Thriller"), new: String::from("Thriller") }],
+        }]);
+        let result = diff(&[], &old);
+        assert_eq!((result.added.len(), result.removed.len()), (2, 0));
+    }
+}
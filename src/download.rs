@@ -0,0 +1,175 @@
+//! Downloading all products of a category, page by page
+
+use std::fs;
+use std::io::{self, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::{write_products_ndjson, DefaultTransport, EANSearch, EANSearchError, ErrorKind, Operation, PageRequest, Product, Transport};
+
+/// Pacing and resume point of a category download
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DownloadOptions {
+    /// The first page to fetch, eg. the checkpoint of an interrupted download
+    pub start_page: PageRequest,
+    /// Minimum time between two page requests
+    pub pause: Duration,
+    /// Stop after this many pages (None for all pages)
+    pub max_pages: Option<u32>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self { start_page: PageRequest::default(), pause: Duration::from_millis(500), max_pages: None }
+    }
+}
+
+/// A page of products of a download
+#[derive(Clone, Debug)]
+pub struct DownloadPage {
+    pub page: PageRequest,
+    pub products: Vec<Product>,
+}
+
+/// Iterator over the pages of a category, see [`EANSearch::download_category_pages`]
+///
+/// The iteration ends after the last page (as the API reports it, or with the first empty page),
+/// after `max_pages` or after the first error.
+/// [`checkpoint`](Self::checkpoint) is the page to resume from after an interruption.
+pub struct CategoryDownload<'a, T: Transport = DefaultTransport> {
+    eansearch: &'a EANSearch<T>,
+    next_op: Option<Operation>,
+    pause: Duration,
+    pages_left: Option<u32>,
+    last_request: Option<Instant>,
+}
+
+impl<'a, T: Transport> CategoryDownload<'a, T> {
+    pub(crate) fn new(eansearch: &'a EANSearch<T>, category: i32, language: Option<i8>, options: DownloadOptions) -> Self {
+        let op = Operation::CategorySearch { category, name: None, language, page: options.start_page };
+        Self { eansearch, next_op: Some(op), pause: options.pause, pages_left: options.max_pages, last_request: None }
+    }
+
+    /// The next page to fetch (None when the download is complete)
+    pub fn checkpoint(&self) -> Option<PageRequest> {
+        self.next_op.as_ref().and_then(Operation::page)
+    }
+}
+
+impl<T: Transport> Iterator for CategoryDownload<'_, T> {
+    type Item = Result<DownloadPage, EANSearchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pages_left == Some(0) {
+            return None;
+        }
+        let op = self.next_op.as_ref()?;
+        if let Some(wait) = self.last_request.and_then(|last| self.pause.checked_sub(last.elapsed())) {
+            std::thread::sleep(wait);
+        }
+        self.last_request = Some(Instant::now());
+        let page = op.page()?;
+        match self.eansearch.list_page(op) {
+            Ok((products, _)) if products.is_empty() => {
+                self.next_op = None;
+                None
+            }
+            Ok((products, more)) => {
+                // don't spend a credit and a pause on the empty page after the last one
+                self.next_op = if more == Some(false) { None } else { op.next_page() };
+                self.pages_left = self.pages_left.map(|n| n - 1);
+                Some(Ok(DownloadPage { page, products }))
+            }
+            Err(e) => {
+                // keep the checkpoint at the failed page, so a retry fetches it again
+                self.pages_left = Some(0);
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// The cursor file next to a download file, eg. "music.ndjson.cursor"
+pub(crate) fn cursor_path(path: &Path) -> PathBuf {
+    let mut cursor = path.as_os_str().to_owned();
+    cursor.push(".cursor");
+    PathBuf::from(cursor)
+}
+
+/// The resume point of a download: the next page, and the file length after the last complete page
+fn read_cursor(path: &Path) -> io::Result<Option<(PageRequest, u64)>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid download cursor {:?}", text));
+    let (page, length) = text.trim().split_once(' ').ok_or_else(invalid)?;
+    let page = page.parse().ok().and_then(|page| PageRequest::new(page).ok()).ok_or_else(invalid)?;
+    Ok(Some((page, length.parse().map_err(|_e| invalid())?)))
+}
+
+fn write_cursor(path: &Path, page: PageRequest, length: u64) -> io::Result<()> {
+    // write and rename, so a crash can't leave a truncated cursor
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, format!("{} {}", page.page(), length))?;
+    fs::rename(&tmp, path)
+}
+
+/// Download a category into an NDJSON file, see [`EANSearch::download_category_to`]
+pub(crate) fn to_file<T: Transport>(eansearch: &EANSearch<T>, path: &Path, category: i32, language: Option<i8>, mut options: DownloadOptions) -> Result<u64, EANSearchError> {
+    let cursor = cursor_path(path);
+    let mut file = match read_cursor(&cursor).map_err(ErrorKind::Io)? {
+        Some((page, length)) => {
+            // drop anything written after the last complete page
+            let mut file = fs::OpenOptions::new().write(true).open(path).map_err(ErrorKind::Io)?;
+            file.set_len(length).map_err(ErrorKind::Io)?;
+            file.seek(io::SeekFrom::End(0)).map_err(ErrorKind::Io)?;
+            options.start_page = page;
+            file
+        }
+        None => fs::File::create(path).map_err(ErrorKind::Io)?,
+    };
+    let mut count = 0;
+    let mut download = CategoryDownload::new(eansearch, category, language, options);
+    while let Some(page) = download.next() {
+        let page = page?;
+        let mut writer = io::BufWriter::new(&mut file);
+        write_products_ndjson(&mut writer, &page.products).map_err(ErrorKind::Io)?;
+        drop(writer);
+        count += page.products.len() as u64;
+        let length = file.stream_position().map_err(ErrorKind::Io)?;
+        match download.checkpoint() {
+            Some(next) => write_cursor(&cursor, next, length).map_err(ErrorKind::Io)?,
+            None => break,
+        }
+    }
+    file.flush().map_err(ErrorKind::Io)?;
+    // the cursor stays when the download stopped at max_pages, so the next call continues
+    if download.checkpoint().is_none() {
+        if let Err(e) = fs::remove_file(&cursor) {
+            if e.kind() != io::ErrorKind::NotFound {
+                return Err(ErrorKind::Io(e).into());
+            }
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let path = std::env::temp_dir().join(format!("eansearch-download-{}.ndjson", std::process::id()));
+        let cursor = cursor_path(&path);
+        assert!(cursor.to_string_lossy().ends_with(".ndjson.cursor"));
+        assert_eq!(read_cursor(&cursor).unwrap(), None);
+        write_cursor(&cursor, PageRequest::new(7).unwrap(), 12345).unwrap();
+        assert_eq!(read_cursor(&cursor).unwrap(), Some((PageRequest::new(7).unwrap(), 12345)));
+        fs::write(&cursor, "7").unwrap();
+        assert_eq!(read_cursor(&cursor).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&cursor).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+//! Dumping raw requests and responses for troubleshooting, see [`EANSearch::debug_dump`](crate::EANSearch::debug_dump)
+
+use std::fmt::Display;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Writes requests (token redacted) and raw response bodies to a sink
+pub(crate) struct DebugDump {
+    sink: Mutex<Box<dyn Write + Send>>,
+}
+
+impl DebugDump {
+    pub(crate) fn new(sink: Box<dyn Write + Send>) -> Self {
+        Self { sink: Mutex::new(sink) }
+    }
+
+    pub(crate) fn request(&self, operation: &str, url: &str, request_id: &str) {
+        self.write(format_args!(">>> {}: GET {} (request {})\n", operation, crate::redact_token(url), request_id));
+    }
+
+    pub(crate) fn response(&self, operation: &str, status: u16, body: &[u8]) {
+        self.write(format_args!("<<< {}: HTTP {} ({} bytes)\n{}\n", operation, status, body.len(), String::from_utf8_lossy(body)));
+    }
+
+    pub(crate) fn failure(&self, operation: &str, error: &dyn Display) {
+        self.write(format_args!("<<< {}: request failed: {}\n", operation, crate::redact_token(&error.to_string())));
+    }
+
+    // the dump is a troubleshooting aid, so a failing sink doesn't fail the API call
+    fn write(&self, args: std::fmt::Arguments) {
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = sink.write_fmt(args).and_then(|()| sink.flush());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_dump() {
+        let buffer = Buffer::default();
+        let dump = DebugDump::new(Box::new(buffer.clone()));
+        dump.request("account-status", "https://api.ean-search.org/api?format=json&token=secret&op=account-status", "0123456789abcdef");
+        dump.response("account-status", 200, br#"{"id":"12345"}"#);
+        dump.failure("account-status", &"connection refused for token=secret");
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(text, concat!(
+            ">>> account-status: GET https://api.ean-search.org/api?format=json&token=***&op=account-status (request 0123456789abcdef)\n",
+            "<<< account-status: HTTP 200 (14 bytes)\n{\"id\":\"12345\"}\n",
+            "<<< account-status: request failed: connection refused for token=***\n",
+        ));
+    }
+}
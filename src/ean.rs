@@ -0,0 +1,393 @@
+//! EAN / GTIN codes with validated check digit, and the EAN-2 / EAN-5 add-ons of scanned codes
+
+use std::error::Error;
+use std::fmt;
+
+/// A GTIN with a valid check digit: EAN-8, UPC-A (12 digits), EAN-13 or GTIN-14
+///
+/// The number of digits is kept, so leading zeros survive, eg. for UPC-A codes or EAN-8 codes
+/// starting with 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ean {
+    code: u64,
+    digits: u8,
+}
+
+/// Error for a string or number that isn't a valid GTIN
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidEan {
+    /// The code doesn't have 8, 12, 13 or 14 digits
+    Length(usize),
+    /// The code contains a character that isn't a digit
+    NotADigit(char),
+    /// The last digit isn't the check digit of the others
+    CheckDigit { expected: u8, found: u8 },
+    /// A UPC-E code has a number system other than 0 or 1
+    InvalidNumberSystem(u8),
+}
+
+impl fmt::Display for InvalidEan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidEan::Length(len) => write!(f, "invalid EAN length {} (must be 8, 12, 13 or 14 digits)", len),
+            InvalidEan::NotADigit(c) => write!(f, "invalid character {:?} in EAN", c),
+            InvalidEan::CheckDigit { expected, found } => write!(f, "invalid EAN check digit {} (expected {})", found, expected),
+            InvalidEan::InvalidNumberSystem(ns) => write!(f, "invalid UPC-E number system {} (must be 0 or 1)", ns),
+        }
+    }
+}
+
+impl Error for InvalidEan {}
+
+/// The GS1 check digit for the digits of a code without its check digit
+pub(crate) fn check_digit(payload: &[u8]) -> u8 {
+    let sum : u32 = payload.iter().rev().enumerate()
+        .map(|(i, &d)| u32::from(d) * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+impl Ean {
+    /// Parse an EAN-8, UPC-A, EAN-13 or GTIN-14 code (digits only) and verify its check digit
+    pub fn parse(code: &str) -> Result<Ean, InvalidEan> {
+        if let Some(c) = code.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(InvalidEan::NotADigit(c));
+        }
+        if ![8, 12, 13, 14].contains(&code.len()) {
+            return Err(InvalidEan::Length(code.len()));
+        }
+        let digits : Vec<u8> = code.bytes().map(|b| b - b'0').collect();
+        let (&found, payload) = digits.split_last().ok_or(InvalidEan::Length(0))?;
+        let expected = check_digit(payload);
+        if found != expected {
+            return Err(InvalidEan::CheckDigit { expected, found });
+        }
+        Ok(Ean { code: code.parse().map_err(|_e| InvalidEan::Length(code.len()))?, digits: code.len() as u8 })
+    }
+
+    /// Complete a code without check digit (7, 11, 12 or 13 digits) by appending the check digit
+    pub fn with_check_digit(payload: &str) -> Result<Ean, InvalidEan> {
+        if let Some(c) = payload.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(InvalidEan::NotADigit(c));
+        }
+        let digits : Vec<u8> = payload.bytes().map(|b| b - b'0').collect();
+        Ean::parse(&format!("{}{}", payload, check_digit(&digits)))
+    }
+
+    /// The code with this number of digits for a payload without check digit (payload must fit)
+    pub(crate) fn complete(payload: u64, digits: u8) -> Ean {
+        let payload_digits : Vec<u8> = format!("{:0>width$}", payload, width = usize::from(digits) - 1)
+            .bytes().map(|b| b - b'0').collect();
+        Ean { code: payload * 10 + u64::from(check_digit(&payload_digits)), digits }
+    }
+
+    /// An EAN-13 code (or GTIN-14 for numbers with 14 digits), as used by the API methods
+    pub fn new(code: u64) -> Result<Ean, InvalidEan> {
+        let digits = if code >= 10_000_000_000_000 { 14 } else { 13 };
+        Self::parse(&format!("{:0>width$}", code, width = digits))
+    }
+
+    /// Expand an 8 digit UPC-E code (number system 0 or 1, 6 digits, check digit) into its UPC-A code
+    pub fn from_upce(code: &str) -> Result<Ean, InvalidEan> {
+        if let Some(c) = code.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(InvalidEan::NotADigit(c));
+        }
+        if code.len() != 8 {
+            return Err(InvalidEan::Length(code.len()));
+        }
+        let (ns, d, check) = (&code[..1], &code[1..7], &code[7..]);
+        if ns != "0" && ns != "1" {
+            return Err(InvalidEan::InvalidNumberSystem(code.as_bytes()[0] - b'0'));
+        }
+        let body = match &d[5..] {
+            "0" | "1" | "2" => format!("{}{}0000{}", &d[..2], &d[5..], &d[2..5]),
+            "3" => format!("{}00000{}", &d[..3], &d[3..5]),
+            "4" => format!("{}00000{}", &d[..4], &d[4..5]),
+            _ => format!("{}0000{}", &d[..5], &d[5..]),
+        };
+        Ean::parse(&format!("{}{}{}", ns, body, check))
+    }
+
+    /// Parse a code in a const context, see [`ean!`](crate::ean)
+    ///
+    /// # Panics
+    ///
+    /// If the code isn't valid, which is a compile error when evaluated as a constant.
+    #[doc(hidden)]
+    pub const fn from_literal(code: &str) -> Ean {
+        let bytes = code.as_bytes();
+        if !matches!(bytes.len(), 8 | 12 | 13 | 14) {
+            panic!("invalid EAN length (must be 8, 12, 13 or 14 digits)");
+        }
+        let (mut value, mut sum, mut i) = (0u64, 0u32, 0);
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_digit() {
+                panic!("invalid character in EAN");
+            }
+            let digit = (bytes[i] - b'0') as u64;
+            value = value * 10 + digit;
+            if i < bytes.len() - 1 {
+                // weights 3, 1, 3, ... from the right, starting left of the check digit
+                sum += digit as u32 * if (bytes.len() - 2 - i) % 2 == 0 { 3 } else { 1 };
+            }
+            i += 1;
+        }
+        if (10 - sum % 10) % 10 != (bytes[bytes.len() - 1] - b'0') as u32 {
+            panic!("invalid EAN check digit");
+        }
+        Ean { code: value, digits: bytes.len() as u8 }
+    }
+
+    /// The code as number, eg. to pass it to [`EANSearch::barcode_lookup`](crate::EANSearch::barcode_lookup)
+    pub fn code(&self) -> u64 {
+        self.code
+    }
+
+    /// The number of digits (8, 12, 13 or 14)
+    pub fn digits(&self) -> usize {
+        usize::from(self.digits)
+    }
+
+    /// Split a scanned code into the EAN and its EAN-2 / EAN-5 add-on, if there is one
+    ///
+    /// The add-on may follow the EAN directly (eg. "977123456700301") or after a space, '+' or '-'.
+    /// Add-ons are recognized after EAN-13 and UPC-A codes.
+    pub fn split_addon(scan: &str) -> Result<(Ean, Option<AddOn>), InvalidEan> {
+        if let Some((main, addon)) = scan.split_once([' ', '+', '-']) {
+            return Ok((Ean::parse(main)?, Some(AddOn::parse(addon)?)));
+        }
+        let split = |len: usize| -> Result<(Ean, Option<AddOn>), InvalidEan> {
+            Ok((Ean::parse(&scan[..len])?, Some(AddOn::parse(&scan[len..])?)))
+        };
+        match scan.len() {
+            15 | 18 if scan.is_ascii() => split(13),
+            17 if scan.is_ascii() => split(12),
+            // a GTIN-14 or a UPC-A code with EAN-2 add-on
+            14 if scan.is_ascii() => Ean::parse(scan).map(|ean| (ean, None)).or_else(|e| split(12).map_err(|_| e)),
+            _ => Ok((Ean::parse(scan)?, None)),
+        }
+    }
+}
+
+impl fmt::Display for Ean {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:0>width$}", self.code, width = self.digits())
+    }
+}
+
+/// An [`Ean`] from a string literal, validated at compile time
+///
+/// ```
+/// use eansearch::{ean, Ean};
+///
+/// const THRILLER: Ean = ean!("5099750442227");
+/// assert_eq!(THRILLER.code(), 5099750442227);
+/// ```
+///
+/// A wrong length, a non-digit or a wrong check digit doesn't compile:
+///
+/// ```compile_fail
+/// let ean = eansearch::ean!("5099750442228");
+/// ```
+#[macro_export]
+macro_rules! ean {
+    ($code:literal) => {{
+        const EAN: $crate::Ean = $crate::Ean::from_literal($code);
+        EAN
+    }};
+}
+
+impl std::str::FromStr for Ean {
+    type Err = InvalidEan;
+
+    /// Same as [`Ean::parse`]
+    fn from_str(code: &str) -> Result<Ean, InvalidEan> {
+        Ean::parse(code)
+    }
+}
+
+impl TryFrom<u64> for Ean {
+    type Error = InvalidEan;
+
+    /// Same as [`Ean::new`]: an EAN-13 code, or GTIN-14 for numbers with 14 digits
+    fn try_from(code: u64) -> Result<Ean, InvalidEan> {
+        Ean::new(code)
+    }
+}
+
+impl TryFrom<&[u8]> for Ean {
+    type Error = InvalidEan;
+
+    /// Parse the ASCII digits of a scanner buffer, see [`Ean::parse`]
+    ///
+    /// The buffer must hold the code only; use [`scan::clean`](crate::scan::clean) for raw scanner input.
+    fn try_from(code: &[u8]) -> Result<Ean, InvalidEan> {
+        if let Some(&b) = code.iter().find(|b| !b.is_ascii_digit()) {
+            return Err(InvalidEan::NotADigit(char::from(b)));
+        }
+        // only ASCII digits, so this is valid UTF-8
+        Ean::parse(std::str::from_utf8(code).map_err(|_e| InvalidEan::Length(code.len()))?)
+    }
+}
+
+impl From<Ean> for u64 {
+    fn from(ean: Ean) -> u64 {
+        ean.code
+    }
+}
+
+/// Serialized as a zero-padded string, eg. `"0036000291452"`
+impl serde::Serialize for Ean {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserialized from a string (see [`Ean::parse`]) or a number (see [`Ean::new`])
+impl<'de> serde::Deserialize<'de> for Ean {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Ean, D::Error> {
+        struct EanVisitor;
+
+        impl serde::de::Visitor<'_> for EanVisitor {
+            type Value = Ean;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an EAN code as string or number")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, code: &str) -> Result<Ean, E> {
+                Ean::parse(code).map_err(E::custom)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, code: u64) -> Result<Ean, E> {
+                Ean::new(code).map_err(E::custom)
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, code: i64) -> Result<Ean, E> {
+                let code = u64::try_from(code).map_err(|_e| E::custom(InvalidEan::NotADigit('-')))?;
+                self.visit_u64(code)
+            }
+        }
+
+        deserializer.deserialize_any(EanVisitor)
+    }
+}
+
+/// The supplemental 2 or 5 digit code printed next to some EAN-13 / UPC-A barcodes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddOn {
+    /// EAN-2, eg. the issue number of a magazine
+    Ean2(u8),
+    /// EAN-5, eg. the suggested retail price of a book
+    Ean5(u32),
+}
+
+impl AddOn {
+    /// Parse a 2 or 5 digit add-on
+    pub fn parse(code: &str) -> Result<AddOn, InvalidEan> {
+        if let Some(c) = code.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(InvalidEan::NotADigit(c));
+        }
+        match code.len() {
+            2 => Ok(AddOn::Ean2(code.parse().map_err(|_e| InvalidEan::Length(2))?)),
+            5 => Ok(AddOn::Ean5(code.parse().map_err(|_e| InvalidEan::Length(5))?)),
+            len => Err(InvalidEan::Length(len)),
+        }
+    }
+}
+
+impl fmt::Display for AddOn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddOn::Ean2(code) => write!(f, "{:02}", code),
+            AddOn::Ean5(code) => write!(f, "{:05}", code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let ean = Ean::parse("5099750442227").unwrap();
+        assert_eq!(ean.code(), 5099750442227);
+        assert_eq!(ean.digits(), 13);
+        assert_eq!(Ean::parse("96385074").unwrap().to_string(), "96385074");
+        assert_eq!(Ean::parse("036000291452").unwrap().to_string(), "036000291452"); // UPC-A
+        assert_eq!(Ean::parse("05099750442227").unwrap().digits(), 14);
+        assert_eq!(Ean::parse("5099750442228"), Err(InvalidEan::CheckDigit { expected: 7, found: 8 }));
+        assert_eq!(Ean::parse("509975044222"), Err(InvalidEan::CheckDigit { expected: 9, found: 2 }));
+        assert_eq!(Ean::parse("12345"), Err(InvalidEan::Length(5)));
+        assert_eq!(Ean::parse(""), Err(InvalidEan::Length(0)));
+        assert_eq!(Ean::parse("50997504422x7"), Err(InvalidEan::NotADigit('x')));
+        assert_eq!(Ean::new(36000291452).unwrap().to_string(), "0036000291452");
+        assert_eq!(Ean::with_check_digit("509975044222"), Ok(ean));
+        assert_eq!(Ean::with_check_digit("9638507").unwrap().to_string(), "96385074");
+        assert_eq!(Ean::with_check_digit("12345"), Err(InvalidEan::Length(6)));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let ean = Ean::parse("4006381333931").unwrap();
+        assert_eq!("4006381333931".parse::<Ean>(), Ok(ean));
+        assert_eq!("400638133393".parse::<Ean>(), Err(InvalidEan::CheckDigit { expected: 0, found: 3 }));
+        assert_eq!(Ean::try_from(4006381333931u64), Ok(ean));
+        assert_eq!(Ean::try_from(4006381333932u64), Err(InvalidEan::CheckDigit { expected: 1, found: 2 }));
+        assert_eq!(Ean::try_from(123_456_789_012_345u64), Err(InvalidEan::Length(15)));
+        assert_eq!(Ean::try_from(&b"4006381333931"[..]), Ok(ean));
+        assert_eq!(Ean::try_from(&b"4006381333931\r"[..]), Err(InvalidEan::NotADigit('\r')));
+        assert_eq!(Ean::try_from(&b"4006\xff"[..]), Err(InvalidEan::NotADigit('\u{ff}')));
+        assert_eq!(Ean::try_from(&b"40063"[..]), Err(InvalidEan::Length(5)));
+    }
+
+    #[test]
+    fn test_ean_macro() {
+        assert_eq!(crate::ean!("5099750442227"), Ean::parse("5099750442227").unwrap());
+        assert_eq!(crate::ean!("96385074"), Ean::parse("96385074").unwrap());
+        assert_eq!(crate::ean!("036000291452").to_string(), "036000291452");
+        assert_eq!(crate::ean!("05099750442227").digits(), 14);
+    }
+
+    #[test]
+    fn test_serde() {
+        let ean = Ean::parse("036000291452").unwrap();
+        assert_eq!(serde_json::to_string(&ean).unwrap(), r#""036000291452""#);
+        assert_eq!(serde_json::from_str::<Ean>(r#""036000291452""#).unwrap(), ean);
+        assert_eq!(serde_json::from_str::<Ean>("4006381333931").unwrap(), Ean::parse("4006381333931").unwrap());
+        let e = serde_json::from_str::<Ean>(r#""4006381333932""#).unwrap_err();
+        assert_eq!(e.to_string(), "invalid EAN check digit 2 (expected 1) at line 1 column 15");
+        assert!(serde_json::from_str::<Ean>("-4006381333931").is_err());
+        assert!(serde_json::from_str::<Ean>("4006381333931.0").is_err());
+    }
+
+    #[test]
+    fn test_from_upce() {
+        assert_eq!(Ean::from_upce("04252614").unwrap().to_string(), "042100005264");
+        assert_eq!(Ean::from_upce("01234565").unwrap().to_string(), "012345000065");
+        assert_eq!(Ean::from_upce("01234531").unwrap().to_string(), "012300000451");
+        assert_eq!(Ean::from_upce("01234543").unwrap().to_string(), "012340000053");
+        assert_eq!(Ean::from_upce("04252615"), Err(InvalidEan::CheckDigit { expected: 4, found: 5 }));
+        assert_eq!(Ean::from_upce("54252614"), Err(InvalidEan::InvalidNumberSystem(5)));
+        assert_eq!(InvalidEan::InvalidNumberSystem(5).to_string(), "invalid UPC-E number system 5 (must be 0 or 1)");
+        assert_eq!(Ean::from_upce("0425261"), Err(InvalidEan::Length(7)));
+    }
+
+    #[test]
+    fn test_split_addon() {
+        let (ean, addon) = Ean::split_addon("977123456700301").unwrap();
+        assert_eq!(ean.to_string(), "9771234567003");
+        assert_eq!(addon, Some(AddOn::Ean2(1)));
+        let (ean, addon) = Ean::split_addon("9780306406157 51299").unwrap();
+        assert_eq!(ean.code(), 9780306406157);
+        assert_eq!(addon.unwrap().to_string(), "51299");
+        let (ean, addon) = Ean::split_addon("03600029145205").unwrap(); // UPC-A + EAN-2
+        assert_eq!((ean.digits(), addon), (12, Some(AddOn::Ean2(5))));
+        assert_eq!(Ean::split_addon("05099750442227").unwrap(), (Ean::parse("05099750442227").unwrap(), None));
+        assert_eq!(Ean::split_addon("5099750442227").unwrap().1, None);
+        assert_eq!(Ean::split_addon("9780306406157+512"), Err(InvalidEan::Length(3)));
+        assert!(Ean::split_addon("977123456700401").is_err()); // invalid check digit in the main code
+        assert!(Ean::split_addon("ääääääää").is_err());
+    }
+}
@@ -0,0 +1,392 @@
+//! The error type returned by the API methods
+
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// The API call an error happened in
+#[derive(Clone, Debug)]
+pub struct CallContext {
+    /// The API operation, eg. "barcode-lookup"
+    pub operation: String,
+    /// The parameters of the operation (without the API token), eg. `[("ean", "5099750442227")]`
+    pub params: Vec<(&'static str, String)>,
+    /// The request URL with the API token redacted
+    pub url: String,
+    /// The ID sent with the request, see [`REQUEST_ID_HEADER`](crate::REQUEST_ID_HEADER)
+    pub request_id: String,
+    /// The HTTP status of the response (None if there was no response, eg. on a network failure)
+    pub status: Option<u16>,
+    /// The start of the response body (up to 200 bytes), to diagnose responses that couldn't be parsed
+    pub body: Option<String>,
+}
+
+impl CallContext {
+    #[cfg(any(feature = "_client", feature = "_async"))]
+    pub(crate) fn new(op: &crate::Operation, request: crate::HttpRequest, response: Option<&crate::HttpResponse>) -> Self {
+        Self {
+            operation: op.name().to_string(),
+            params: op.params(),
+            url: request.redacted_url(),
+            request_id: request.request_id,
+            status: response.map(|response| response.status),
+            body: response.map(|response| crate::parse::excerpt(&String::from_utf8_lossy(&response.body))),
+        }
+    }
+}
+
+impl fmt::Display for CallContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            write!(f, "{}{}={}", if i == 0 { " " } else { ", " }, name, value)?;
+        }
+        write!(f, "; request {}", self.request_id)?;
+        match self.status {
+            Some(status) => write!(f, ", HTTP {}", status),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The kind of error that occurred
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The HTTP request failed or the response couldn't be read (see [`crate::reqwest`])
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    Http(reqwest::Error),
+    /// The API returned an error message, eg. "Barcode not found" (see [`ApiErrorKind`])
+    Api(String),
+    /// The API token is wrong (with the API's message), eg. to fail over to another token
+    InvalidToken(String),
+    /// The account has no credits left (with the API's message), the token works again once the quota is renewed
+    QuotaExhausted(String),
+    /// The response wasn't in the expected format
+    Json(serde_json::Error),
+    /// The response was neither a result nor an API error message
+    UndefinedApiError,
+    /// The response didn't have the expected structure (eg. a maintenance page instead of a product list)
+    UnexpectedResponse { operation: String, excerpt: String },
+    /// The response has fields the parsers don't know (only reported in strict mode)
+    UnknownFields { operation: String, fields: Vec<String> },
+    /// The barcode image in the response wasn't valid base64
+    #[cfg(feature = "image")]
+    Base64(base64::DecodeError),
+    /// The barcode image in the response isn't a PNG image, eg. an error message
+    #[cfg(feature = "image")]
+    InvalidImage(&'static str),
+    /// A custom [`Transport`](crate::Transport) failed to send the request or read the response
+    Transport(Box<dyn Error + Send + Sync>),
+    /// The API answered with HTTP status 429 (too many requests), with the delay it asked for in the `Retry-After` header
+    RateLimited { retry_after: Option<Duration> },
+    /// A method was called with an argument it can't handle
+    InvalidArgument(String),
+    /// No request was sent because the credits dropped below the hard threshold of the [`QuotaGuard`](crate::QuotaGuard)
+    QuotaGuardTripped { credits_remaining: u32, threshold: u32 },
+    /// A configuration profile couldn't be loaded
+    Config(String),
+    /// A file couldn't be read or written, eg. for a download
+    Io(std::io::Error),
+    /// A snapshot couldn't be recorded in the [`SnapshotStore`](crate::SnapshotStore)
+    Snapshot(std::io::Error),
+    /// An Excel workbook couldn't be written
+    #[cfg(feature = "xlsx")]
+    Xlsx(rust_xlsxwriter::XlsxError),
+    /// The API token couldn't be read from the OS keyring
+    #[cfg(feature = "keyring")]
+    Keyring(keyring::Error),
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(any(feature = "blocking", feature = "async"))]
+            ErrorKind::Http(e) => write!(f, "HTTP request failed: {}", crate::redact_token(&e.to_string())),
+            ErrorKind::Api(msg) | ErrorKind::InvalidToken(msg) | ErrorKind::QuotaExhausted(msg) => write!(f, "{}", msg),
+            ErrorKind::Json(e) => write!(f, "invalid response: {}", e),
+            ErrorKind::UndefinedApiError => write!(f, "Undefined API error"),
+            ErrorKind::UnexpectedResponse { operation, excerpt } =>
+                write!(f, "unexpected response to {}: {}", operation, excerpt),
+            ErrorKind::UnknownFields { operation, fields } =>
+                write!(f, "unknown fields in response to {}: {}", operation, fields.join(", ")),
+            #[cfg(feature = "image")]
+            ErrorKind::Base64(e) => write!(f, "invalid barcode image: {}", e),
+            #[cfg(feature = "image")]
+            ErrorKind::InvalidImage(reason) => write!(f, "invalid barcode image: {}", reason),
+            ErrorKind::Transport(e) => write!(f, "HTTP request failed: {}", crate::redact_token(&e.to_string())),
+            ErrorKind::RateLimited { retry_after: Some(delay) } => write!(f, "rate limited, retry after {}s", delay.as_secs()),
+            ErrorKind::RateLimited { retry_after: None } => write!(f, "rate limited"),
+            ErrorKind::InvalidArgument(msg) => write!(f, "{}", msg),
+            ErrorKind::QuotaGuardTripped { credits_remaining, threshold } =>
+                write!(f, "quota guard tripped: {} credits remaining (hard limit {})", credits_remaining, threshold),
+            ErrorKind::Config(msg) => write!(f, "configuration: {}", msg),
+            ErrorKind::Io(e) => write!(f, "I/O error: {}", e),
+            ErrorKind::Snapshot(e) => write!(f, "snapshot store: {}", e),
+            #[cfg(feature = "xlsx")]
+            ErrorKind::Xlsx(e) => write!(f, "xlsx export: {}", e),
+            #[cfg(feature = "keyring")]
+            ErrorKind::Keyring(e) => write!(f, "keyring: {}", e),
+        }
+    }
+}
+
+/// The errors the API reports with a message (see [`ErrorKind::Api`]), so code doesn't depend on their wording
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ApiErrorKind {
+    /// The barcode is valid but not in the database
+    BarcodeNotFound,
+    /// The barcode isn't a valid EAN, UPC or ISBN code
+    InvalidBarcode,
+    /// The API token is wrong
+    InvalidToken,
+    /// Too many requests were sent in a short time (reported as the retryable [`ErrorKind::RateLimited`])
+    TooManyRequests,
+    /// The account has no credits left (recognized by the credits header, see [`ErrorKind::QuotaExhausted`])
+    QuotaExhausted,
+    /// A message the client doesn't know (yet)
+    Other,
+}
+
+/// The known messages of the API, compared without case and surrounding whitespace
+const API_MESSAGES: &[(&str, ApiErrorKind)] = &[
+    ("Barcode not found", ApiErrorKind::BarcodeNotFound),
+    ("Invalid barcode", ApiErrorKind::InvalidBarcode),
+    ("Invalid token", ApiErrorKind::InvalidToken),
+    ("Too many requests", ApiErrorKind::TooManyRequests),
+];
+
+impl ApiErrorKind {
+    /// The kind of an error message returned by the API
+    pub fn from_message(msg: &str) -> Self {
+        let msg = msg.trim();
+        API_MESSAGES.iter().find(|(known, _)| known.eq_ignore_ascii_case(msg)).map_or(ApiErrorKind::Other, |(_, kind)| *kind)
+    }
+}
+
+/// An error returned by the EANSearch methods, with the API call it happened in
+#[derive(Debug)]
+pub struct EANSearchError {
+    kind: ErrorKind,
+    // boxed to keep Result<T, EANSearchError> small
+    context: Option<Box<CallContext>>,
+}
+
+impl EANSearchError {
+    pub(crate) fn with_context(kind: ErrorKind, context: CallContext) -> Self {
+        Self { kind, context: Some(Box::new(context)) }
+    }
+
+    /// What went wrong
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// The API call that failed (None for errors that didn't happen during an API call)
+    pub fn context(&self) -> Option<&CallContext> {
+        self.context.as_deref()
+    }
+
+    /// Whether the error is likely transient, so the call may succeed when retried
+    /// (network problems, timeouts, rate limits and unexpected responses like maintenance pages)
+    pub fn is_retryable(&self) -> bool {
+        match &self.kind {
+            #[cfg(all(any(feature = "blocking", feature = "async"), not(target_arch = "wasm32")))]
+            ErrorKind::Http(e) => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body(),
+            // fetch doesn't tell connection errors apart
+            #[cfg(all(feature = "async", target_arch = "wasm32"))]
+            ErrorKind::Http(e) => e.is_timeout() || e.is_request() || e.is_body(),
+            ErrorKind::Transport(_) | ErrorKind::UnexpectedResponse { .. } | ErrorKind::RateLimited { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the API found nothing for the request
+    ///
+    /// Lookups return `Ok(None)` and searches an empty list in this case, so it only happens for operations
+    /// with a single result that can't be empty, like [`issuing_country`](crate::EANSearch::issuing_country)
+    /// and [`barcode_image`](crate::EANSearch::barcode_image).
+    pub fn is_not_found(&self) -> bool {
+        self.kind.is_not_found()
+    }
+
+    /// How long the API asked to wait before the next request, if it rate limited this one
+    pub fn retry_after(&self) -> Option<Duration> {
+        match &self.kind {
+            ErrorKind::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// The error message returned by the API, if this is an API error
+    pub fn api_message(&self) -> Option<&str> {
+        match &self.kind {
+            ErrorKind::Api(msg) | ErrorKind::InvalidToken(msg) | ErrorKind::QuotaExhausted(msg) => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// What the API error message means, if this is an API error
+    pub fn api_error_kind(&self) -> Option<ApiErrorKind> {
+        match &self.kind {
+            ErrorKind::InvalidToken(_) => Some(ApiErrorKind::InvalidToken),
+            ErrorKind::QuotaExhausted(_) => Some(ApiErrorKind::QuotaExhausted),
+            ErrorKind::RateLimited { .. } => Some(ApiErrorKind::TooManyRequests),
+            _ => self.api_message().map(ApiErrorKind::from_message),
+        }
+    }
+}
+
+impl ErrorKind {
+    /// Whether the API found nothing for the request, see [`EANSearchError::is_not_found`]
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ErrorKind::Api(msg) if ApiErrorKind::from_message(msg) == ApiErrorKind::BarcodeNotFound)
+    }
+
+    /// The error for a message returned by the API
+    pub(crate) fn api(msg: String) -> Self {
+        match ApiErrorKind::from_message(&msg) {
+            ApiErrorKind::InvalidToken => ErrorKind::InvalidToken(msg),
+            // the message can come without the 429 status, it's still worth retrying
+            ApiErrorKind::TooManyRequests => ErrorKind::RateLimited { retry_after: None },
+            _ => ErrorKind::Api(msg),
+        }
+    }
+}
+
+impl From<ErrorKind> for EANSearchError {
+    fn from(kind: ErrorKind) -> Self {
+        Self { kind, context: None }
+    }
+}
+
+impl fmt::Display for EANSearchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.context() {
+            // the body explains responses that couldn't be deserialized
+            Some(context @ CallContext { body: Some(body), .. }) if matches!(self.kind, ErrorKind::UndefinedApiError | ErrorKind::Json(_)) =>
+                write!(f, "{} ({}): {}", self.kind, context, body),
+            Some(context) => write!(f, "{} ({})", self.kind, context),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl Error for EANSearchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            #[cfg(any(feature = "blocking", feature = "async"))]
+            ErrorKind::Http(e) => Some(e),
+            ErrorKind::Json(e) => Some(e),
+            ErrorKind::Transport(e) => Some(e.as_ref()),
+            #[cfg(feature = "image")]
+            ErrorKind::Base64(e) => Some(e),
+            ErrorKind::Io(e) | ErrorKind::Snapshot(e) => Some(e),
+            #[cfg(feature = "xlsx")]
+            ErrorKind::Xlsx(e) => Some(e),
+            #[cfg(feature = "keyring")]
+            ErrorKind::Keyring(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// errors have to cross thread boundaries (std::thread, tokio::spawn, rayon), and so does the client
+const _: () = {
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+    fn assert_all() {
+        assert_send_sync::<EANSearchError>();
+        #[cfg(feature = "_client")]
+        assert_send_sync::<crate::EANSearch>();
+        #[cfg(feature = "_async")]
+        assert_send_sync::<crate::AsyncEANSearch>();
+    }
+};
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+impl From<reqwest::Error> for ErrorKind {
+    fn from(e: reqwest::Error) -> Self {
+        // the URL has the API token
+        ErrorKind::Http(e.without_url())
+    }
+}
+
+impl From<serde_json::Error> for ErrorKind {
+    fn from(e: serde_json::Error) -> Self {
+        ErrorKind::Json(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let context = CallContext {
+            operation: String::from("barcode-lookup"),
+            params: vec![("ean", String::from("5099750442227")), ("language", String::from("1"))],
+            url: String::from("https://api.ean-search.org/api?format=json&token=***&op=barcode-lookup"),
+            request_id: String::from("0123456789abcdef"),
+            status: None,
+            body: None,
+        };
+        let e = EANSearchError::with_context(ErrorKind::UndefinedApiError, CallContext {
+            status: Some(200),
+            body: Some(String::from(r#"[{"error":false}]"#)),
+            ..context.clone()
+        });
+        assert_eq!(e.to_string(), r#"Undefined API error (barcode-lookup ean=5099750442227, language=1; request 0123456789abcdef, HTTP 200): [{"error":false}]"#);
+        let e = EANSearchError::with_context(ErrorKind::Api(String::from("Invalid token")), context);
+        assert_eq!(e.to_string(), "Invalid token (barcode-lookup ean=5099750442227, language=1; request 0123456789abcdef)");
+        assert_eq!(e.api_message(), Some("Invalid token"));
+        assert_eq!(e.api_error_kind(), Some(ApiErrorKind::InvalidToken));
+        let e = EANSearchError::from(ErrorKind::UndefinedApiError);
+        assert_eq!(e.to_string(), "Undefined API error");
+        assert!(e.context().is_none());
+        assert!(!e.is_retryable());
+        let e = EANSearchError::from(ErrorKind::UnexpectedResponse { operation: String::from("product-search"), excerpt: String::from("<html>") });
+        assert_eq!(e.to_string(), "unexpected response to product-search: <html>");
+        let e = EANSearchError::from(ErrorKind::QuotaGuardTripped { credits_remaining: 99, threshold: 100 });
+        assert_eq!(e.to_string(), "quota guard tripped: 99 credits remaining (hard limit 100)");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_token_not_leaked() {
+        // nothing listens on the discard port
+        let eansearch = crate::EANSearch::builder("secret").base_url("http://127.0.0.1:9/api").build().unwrap();
+        let e = eansearch.account_status().unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Http(_)));
+        assert!(!format!("{:?}", e).contains("secret"));
+        let mut source : Option<&dyn Error> = Some(&e);
+        while let Some(error) = source {
+            assert!(!error.to_string().contains("secret"), "{}", error);
+            assert!(!format!("{:?}", error).contains("secret"), "{:?}", error);
+            source = error.source();
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        let handle = std::thread::spawn(|| -> Result<(), Box<dyn Error + Send + Sync>> {
+            Err(EANSearchError::from(ErrorKind::UndefinedApiError).into())
+        });
+        let e = handle.join().unwrap().unwrap_err();
+        assert!(e.downcast_ref::<EANSearchError>().is_some());
+    }
+
+    #[test]
+    fn test_api_error_kind() {
+        assert_eq!(ApiErrorKind::from_message("Barcode not found"), ApiErrorKind::BarcodeNotFound);
+        assert_eq!(ApiErrorKind::from_message(" invalid TOKEN\n"), ApiErrorKind::InvalidToken);
+        assert_eq!(ApiErrorKind::from_message("Something new"), ApiErrorKind::Other);
+        assert_eq!(EANSearchError::from(ErrorKind::UndefinedApiError).api_error_kind(), None);
+        assert!(EANSearchError::from(ErrorKind::Api(String::from("Barcode not found"))).is_not_found());
+        assert!(!EANSearchError::from(ErrorKind::InvalidToken(String::from("Invalid token"))).is_not_found());
+        let e = EANSearchError::from(ErrorKind::api(String::from("Too many requests")));
+        assert!(e.is_retryable());
+        assert_eq!(e.api_error_kind(), Some(ApiErrorKind::TooManyRequests));
+    }
+}
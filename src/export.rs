@@ -0,0 +1,169 @@
+//! Export product lists for other systems
+
+use std::io::{self, Write};
+
+use crate::Product;
+#[cfg(feature = "xlsx")]
+use crate::{EANSearchError, ErrorKind};
+
+/// Write products as an XML document
+///
+/// The schema is stable: new elements may be added to `<product>` in later versions
+/// (with a new `version` attribute), but existing elements won't be renamed or removed.
+/// EANs are always written with 13 digits, including leading zeros.
+/// Version 2 added `<google_category_id>`, written only for products that have one.
+///
+/// ```xml
+/// <?xml version="1.0" encoding="UTF-8"?>
+/// <products version="2">
+///   <product>
+///     <ean>5099750442227</ean>
+///     <name>Michael Jackson: Thriller</name>
+///     <categoryId>45</categoryId>
+///     <categoryName>Music</categoryName>
+///     <issuingCountry>UK</issuingCountry>
+///     <google_category_id>855</google_category_id>
+///   </product>
+/// </products>
+/// ```
+pub fn write_products_xml<W: Write>(mut writer: W, products: &[Product]) -> io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<products version="2">"#)?;
+    for p in products {
+        writeln!(writer, "  <product>")?;
+        writeln!(writer, "    <ean>{:0>13}</ean>", p.ean)?;
+        writeln!(writer, "    <name>{}</name>", xml_escape(&p.name))?;
+        writeln!(writer, "    <categoryId>{}</categoryId>", p.category_id)?;
+        writeln!(writer, "    <categoryName>{}</categoryName>", xml_escape(&p.category_name))?;
+        writeln!(writer, "    <issuingCountry>{}</issuingCountry>", xml_escape(&p.issuing_country))?;
+        if let Some(google_category_id) = p.google_category_id {
+            writeln!(writer, "    <google_category_id>{}</google_category_id>", google_category_id)?;
+        }
+        writeln!(writer, "  </product>")?;
+    }
+    writeln!(writer, "</products>")?;
+    writer.flush()
+}
+
+/// Write products as newline-delimited JSON, one object per line
+///
+/// The objects have the same keys as the API responses (so they can be read back as [`Product`]),
+/// with EANs as 13 digit strings, including leading zeros.
+pub fn write_products_ndjson<W: Write>(mut writer: W, products: &[Product]) -> io::Result<()> {
+    for p in products {
+        let mut value = serde_json::to_value(p)?;
+        value["ean"] = serde_json::Value::String(format!("{:0>13}", p.ean));
+        serde_json::to_writer(&mut writer, &value)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Escape text for XML element content, replacing characters XML 1.0 doesn't allow
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => escaped.push(char::REPLACEMENT_CHARACTER),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write products as an Excel workbook with a header row and one row per product
+///
+/// The columns are EAN, name, category ID, category name, issuing country and Google category ID
+/// (empty for products without one).
+/// EANs are written as 13 digit text cells, so Excel keeps leading zeros and doesn't
+/// turn them into numbers in scientific notation.
+#[cfg(feature = "xlsx")]
+pub fn write_products_xlsx<W: Write + Send>(writer: W, products: &[Product]) -> Result<(), EANSearchError> {
+    use rust_xlsxwriter::{Format, Workbook};
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    let header = Format::new().set_bold();
+    let text = Format::new().set_num_format("@");
+    for (col, title) in ["EAN", "Name", "Category ID", "Category", "Issuing Country", "Google Category ID"].into_iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, title, &header).map_err(ErrorKind::Xlsx)?;
+    }
+    for (row, p) in (1..).zip(products) {
+        sheet.write_string_with_format(row, 0, format!("{:0>13}", p.ean), &text).map_err(ErrorKind::Xlsx)?;
+        sheet.write_string(row, 1, &p.name).map_err(ErrorKind::Xlsx)?;
+        sheet.write_number(row, 2, p.category_id).map_err(ErrorKind::Xlsx)?;
+        sheet.write_string(row, 3, &p.category_name).map_err(ErrorKind::Xlsx)?;
+        sheet.write_string(row, 4, &p.issuing_country).map_err(ErrorKind::Xlsx)?;
+        if let Some(google_category_id) = p.google_category_id {
+            sheet.write_number(row, 5, google_category_id).map_err(ErrorKind::Xlsx)?;
+        }
+    }
+    sheet.set_column_format(0, &text).map_err(ErrorKind::Xlsx)?;
+    sheet.set_column_width(0, 16).map_err(ErrorKind::Xlsx)?;
+    sheet.set_column_width(1, 50).map_err(ErrorKind::Xlsx)?;
+    workbook.save_to_writer(writer).map_err(ErrorKind::Xlsx)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_products_xml() {
+        let products = vec![Product {
+            ean: 16494600224, name: String::from("Tom & Jerry <Deluxe>\u{1}"), category_id: 45,
+            category_name: String::from("Music"), google_category_id: None, issuing_country: String::from("US"), extra: Default::default(),
+        }, crate::fixtures::product()];
+        let mut buffer = Vec::new();
+        write_products_xml(&mut buffer, &products).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<products version=\"2\">\n",
+            "  <product>\n",
+            "    <ean>0016494600224</ean>\n",
+            "    <name>Tom &amp; Jerry &lt;Deluxe&gt;\u{FFFD}</name>\n",
+            "    <categoryId>45</categoryId>\n",
+            "    <categoryName>Music</categoryName>\n",
+            "    <issuingCountry>US</issuingCountry>\n",
+            "  </product>\n",
+            "  <product>\n",
+            "    <ean>5099750442227</ean>\n",
+            "    <name>Michael Jackson: Thriller</name>\n",
+            "    <categoryId>45</categoryId>\n",
+            "    <categoryName>Music</categoryName>\n",
+            "    <issuingCountry>UK</issuingCountry>\n",
+            "    <google_category_id>855</google_category_id>\n",
+            "  </product>\n",
+            "</products>\n",
+        ));
+    }
+
+    #[test]
+    fn test_write_products_ndjson() {
+        let products = crate::fixtures::product_list();
+        let mut buffer = Vec::new();
+        write_products_ndjson(&mut buffer, &products).unwrap();
+        let ndjson = String::from_utf8(buffer).unwrap();
+        assert_eq!(ndjson.lines().next(), Some(r#"{"categoryId":"45","categoryName":"Music","ean":"0016494600224","issuingCountry":"US","name":"Bananaboat Song"}"#));
+        let read : Vec<Product> = ndjson.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[1].ean, products[1].ean);
+    }
+
+    #[cfg(feature = "xlsx")]
+    #[test]
+    fn test_write_products_xlsx() {
+        let products = vec![Product {
+            ean: 16494600224, name: String::from("Bananaboat Song"), category_id: 45,
+            category_name: String::from("Music"), google_category_id: None, issuing_country: String::from("US"), extra: Default::default(),
+        }, crate::fixtures::product()];
+        let mut buffer = Vec::new();
+        write_products_xlsx(&mut buffer, &products).unwrap();
+        assert!(buffer.starts_with(b"PK")); // xlsx is a zip archive
+    }
+}
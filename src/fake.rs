@@ -0,0 +1,292 @@
+//! A transport that makes up products locally, for load tests (enabled with the `fake` feature)
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::{Allocation, Ean, HttpRequest, HttpResponse, PageRequest, Product};
+#[cfg(any(feature = "_client", feature = "_async"))]
+use crate::ErrorKind;
+#[cfg(feature = "_client")]
+use crate::Transport;
+#[cfg(feature = "_async")]
+use crate::AsyncTransport;
+
+const ADJECTIVES: &[&str] = &["Classic", "Organic", "Deluxe", "Compact", "Vintage", "Wireless", "Family", "Premium"];
+const NOUNS: &[&str] = &["Coffee", "Headphones", "Cookbook", "Sneakers", "Album", "Toolbox", "Backpack", "Tea Set"];
+const CATEGORIES: &[(i32, &str, Option<i32>)] = &[
+    (15, "Books and Magazines", Some(784)),
+    (45, "Music", Some(855)),
+    (1, "Food", None),
+    (4, "Electronics", None),
+    (7, "Clothing and Shoes", None),
+    (10, "Home and Garden", None),
+];
+
+/// Products in each page of search results
+const PAGE_SIZE: u64 = 10;
+/// Pages of search results (later pages are empty)
+const PAGES: u32 = 3;
+/// The image of every barcode: a blank 1x1 PNG
+const BLANK_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABAQAAAAA3bvkkAAAACklEQVR4nGNgAAAAAgABSK+kcQAAAABJRU5ErkJggg";
+
+/// An [`EANSearch`](crate::EANSearch) client that makes up products instead of calling the API, see [`FakeTransport`]
+///
+/// ```
+/// # use eansearch::{FakeEANSearch, FakeTransport};
+/// let eansearch = FakeEANSearch::with_transport("fake", FakeTransport::new());
+/// let product = eansearch.barcode_lookup(5099750442227, None)?; // always the same made-up product
+/// # Ok::<(), eansearch::EANSearchError>(())
+/// ```
+#[cfg(feature = "_client")]
+pub type FakeEANSearch = crate::EANSearch<FakeTransport>;
+
+/// A transport that answers the API requests with made-up products instead of sending them
+///
+/// The data is pseudo-random but deterministic: the same EAN or search always returns the same products,
+/// and about one in ten valid EANs isn't found. Nothing is sent over the network and no credits are used,
+/// so downstream systems can be load-tested at full speed. The responses are parsed like the API's,
+/// so all methods of [`EANSearch`](crate::EANSearch) and [`AsyncEANSearch`](crate::AsyncEANSearch) work with it
+/// (barcode images are blank).
+#[derive(Clone, Debug, Default)]
+pub struct FakeTransport {}
+
+impl FakeTransport {
+    /// A fake transport (there is nothing to configure)
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// The response the API would send for a request, eg. to use the fake data with another HTTP stack
+    pub fn respond(&self, request: &HttpRequest) -> HttpResponse {
+        let body = answer(&params(&request.url));
+        HttpResponse { status: 200, headers: Vec::new(), body: body.to_string().into_bytes() }
+    }
+}
+
+#[cfg(feature = "_client")]
+impl Transport for FakeTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+        Ok(self.respond(request))
+    }
+}
+
+#[cfg(feature = "_async")]
+impl AsyncTransport for FakeTransport {
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+        Ok(self.respond(request))
+    }
+}
+
+/// The decoded query parameters of a request URL
+fn params(url: &str) -> HashMap<String, String> {
+    let query = url.split_once('?').map_or("", |(_, query)| query);
+    query.split('&').filter_map(|param| param.split_once('=')).map(|(name, value)| (decode(name), decode(value))).collect()
+}
+
+/// Undo the percent-encoding of a query value
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok()).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The response body for the parameters of a request
+fn answer(params: &HashMap<String, String>) -> Value {
+    let param = |name: &str| params.get(name).map(String::as_str);
+    let number = |name: &str| param(name).and_then(|value| value.parse::<u64>().ok());
+    let page = param("page").and_then(|page| page.parse().ok()).and_then(|page| PageRequest::new(page).ok()).unwrap_or_default();
+    match param("op").unwrap_or_default() {
+        "barcode-lookup" => {
+            let ean = match (number("ean"), number("isbn")) {
+                (Some(ean), _) => valid(ean),
+                (None, Some(isbn)) => isbn13(isbn),
+                (None, None) => None,
+            };
+            match ean.map(lookup) {
+                Some(Some(product)) => json!([product]),
+                Some(None) => error("Barcode not found"),
+                None => error("Invalid barcode"),
+            }
+        }
+        "barcode-prefix-search" => match number("prefix") {
+            Some(prefix) => product_page(page, prefix_search(prefix, page)),
+            None => error("Invalid prefix"),
+        },
+        "product-search" => product_page(page, search(param("name").unwrap_or_default(), None, page)),
+        "category-search" => match param("category").and_then(|category| category.parse().ok()) {
+            Some(category) => product_page(page, search(param("name").unwrap_or_default(), Some(category), page)),
+            None => error("Invalid category"),
+        },
+        "issuing-country" => match number("ean").and_then(valid) {
+            Some(ean) => json!([{"ean": ean.to_string(), "issuingCountry": issuing_country(ean)}]),
+            None => error("Invalid barcode"),
+        },
+        "verify-checksum" => match number("ean") {
+            Some(ean) => json!([{"ean": ean.to_string(), "valid": if valid(ean).is_some() { "1" } else { "0" }}]),
+            None => error("Invalid barcode"),
+        },
+        "barcode-image" => match number("ean").and_then(valid) {
+            Some(ean) => json!([{"ean": ean.to_string(), "barcode": BLANK_PNG}]),
+            None => error("Invalid barcode"),
+        },
+        // unlimited credits
+        "account-status" => json!({"id": "fake", "requests": 0, "requestlimit": u32::MAX}),
+        _ => error("Invalid operation"),
+    }
+}
+
+fn error(msg: &str) -> Value {
+    json!([{"error": msg}])
+}
+
+/// A page of search results, as the API sends it
+fn product_page(page: PageRequest, products: Vec<Product>) -> Value {
+    json!({
+        "page": page.page().to_string(),
+        "moreproducts": !products.is_empty() && page.page() + 1 < PAGES,
+        "productlist": products,
+    })
+}
+
+fn valid(ean: u64) -> Option<Ean> {
+    Ean::new(ean).ok()
+}
+
+/// The EAN of an ISBN-10 (without its check digit, which may be an X) or ISBN-13
+fn isbn13(isbn: u64) -> Option<Ean> {
+    match isbn {
+        0..=9_999_999_999 => Some(Ean::complete(978_000_000_000 + isbn / 10, 13)),
+        _ => valid(isbn),
+    }
+}
+
+fn lookup(ean: Ean) -> Option<Product> {
+    (mix(ean.code()) % 10 != 0).then(|| product(ean, None, None))
+}
+
+/// The product for an EAN, with keywords in the name and in a category if given
+fn product(ean: Ean, keywords: Option<&str>, category: Option<i32>) -> Product {
+    let seed = mix(ean.code());
+    let noun = NOUNS[(seed >> 8) as usize % NOUNS.len()];
+    let name = match keywords.filter(|keywords| !keywords.trim().is_empty()) {
+        Some(keywords) => format!("{} {}", keywords.trim(), noun),
+        None => format!("{} {}", ADJECTIVES[seed as usize % ADJECTIVES.len()], noun),
+    };
+    let (category_id, category_name, google_category_id) = match category {
+        Some(id) => CATEGORIES.iter().copied().find(|c| c.0 == id).unwrap_or((id, "Other", None)),
+        None => CATEGORIES[(seed >> 16) as usize % CATEGORIES.len()],
+    };
+    Product {
+        ean: ean.code(),
+        name,
+        category_id,
+        category_name: category_name.to_string(),
+        google_category_id,
+        issuing_country: issuing_country(ean),
+        extra: Default::default(),
+    }
+}
+
+fn issuing_country(ean: Ean) -> String {
+    match ean.allocation() {
+        Ok(Allocation::MemberOrganization(country)) => country.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn search(keywords: &str, category: Option<i32>, page: PageRequest) -> Vec<Product> {
+    let seed = keywords.bytes().fold(mix(u64::from(category.unwrap_or_default() as u32)), |h, b| mix(h ^ u64::from(b)));
+    page_eans(page, |i| Ean::complete(400_000_000_000 + mix(seed ^ i) % 100_000_000_000, 13))
+        .map(|ean| product(ean, Some(keywords), category))
+        .collect()
+}
+
+fn prefix_search(prefix: u64, page: PageRequest) -> Vec<Product> {
+    // the prefix plus enough digits for a 12 digit payload
+    let Some(free) = 12u32.checked_sub(prefix.checked_ilog10().unwrap_or(0) + 1) else {
+        return Vec::new();
+    };
+    let range = 10u64.pow(free);
+    page_eans(page, |i| Ean::complete(prefix * range + mix(prefix ^ i) % range, 13))
+        .map(|ean| product(ean, None, None))
+        .collect()
+}
+
+/// The EANs of a page of search results, the `i`th made by `ean(i)`
+fn page_eans(page: PageRequest, ean: impl Fn(u64) -> Ean) -> impl Iterator<Item = Ean> {
+    let first = u64::from(page.page()) * PAGE_SIZE;
+    let count = if page.page() < PAGES { PAGE_SIZE } else { 0 };
+    (first..first + count).map(ean)
+}
+
+/// SplitMix64, to spread similar EANs over the whole range
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params() {
+        let params = params("http://localhost/api?format=json&token=a%26b&op=product-search&name=Caf%C3%A9%20%25");
+        assert_eq!(params["token"], "a&b");
+        assert_eq!(params["name"], "Café %");
+        assert_eq!(decode("100%"), "100%");
+    }
+
+    #[cfg(feature = "_client")]
+    #[test]
+    fn test_fake() {
+        use crate::{EANSearch, Operation, Response};
+
+        let fake = EANSearch::with_transport("fake", FakeTransport::new());
+        let product = fake.barcode_lookup(5099750442227, None).unwrap().unwrap();
+        assert_eq!(product.ean, 5099750442227);
+        assert_eq!(product.issuing_country, "UK");
+        assert_eq!(fake.barcode_lookup(5099750442227, None).unwrap().unwrap().name, product.name);
+        assert_eq!(fake.barcode_lookup(5099750442228, None).unwrap_err().api_message(), Some("Invalid barcode"));
+        let found = (4006381333900..4006381334000).filter_map(|code| fake.barcode_lookup(Ean::complete(code, 13).code(), None).ok())
+            .filter(Option::is_some).count();
+        assert!((80..100).contains(&found));
+        assert!(fake.isbn_lookup(1119578884).is_ok());
+
+        let products = fake.product_search("bananaboat", None, PageRequest::default()).unwrap();
+        assert_eq!(products.len(), 10);
+        assert!(products.iter().all(|p| p.name.starts_with("bananaboat ") && Ean::new(p.ean).is_ok()));
+        assert_eq!(fake.product_search("bananaboat", None, PageRequest::default()).unwrap()[9].ean, products[9].ean);
+        assert!(fake.product_search("bananaboat", None, PageRequest::new(PAGES).unwrap()).unwrap().is_empty());
+        let products = fake.category_search(45, None, None, PageRequest::default()).unwrap();
+        assert!(products.iter().all(|p| p.category_name == "Music"));
+        let products = fake.barcode_prefix_search(4006381, None, PageRequest::default()).unwrap();
+        assert!(products.iter().all(|p| p.ean.to_string().starts_with("4006381") && p.issuing_country == "DE"));
+        assert!(matches!(fake.execute(&Operation::VerifyChecksum { ean: 5099750442228 }), Ok(Response::ChecksumValid(false))));
+        assert_eq!(fake.issuing_country(4006381333931).unwrap(), "DE");
+        assert_eq!(fake.account_status().unwrap(), u32::MAX);
+
+        // all pages, without a request for an empty one
+        let op = Operation::ProductSearch { name: String::from("bananaboat"), language: None, page: PageRequest::default() };
+        assert_eq!(fake.iter_products(op).count(), 30);
+        #[cfg(feature = "image")]
+        assert!(fake.barcode_image(5099750442227, None, None).is_ok());
+    }
+}
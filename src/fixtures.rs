@@ -0,0 +1,86 @@
+//! Response bodies in the format the API returns them, for offline tests
+//!
+//! There is a sample response for every operation (see [`body`]) and for the not-found, API error
+//! and rate-limited cases. The library's own tests use the same samples, so downstream tests and mock
+//! clients stay in sync with what the parsers accept. Available with the `fixtures` feature.
+
+use crate::{Operation, Product, Response};
+
+/// barcode-lookup for 5099750442227
+pub const BARCODE_LOOKUP: &str = r#"[{"ean":"5099750442227","name":"Michael Jackson: Thriller","categoryId":"45","categoryName":"Music","googleCategoryId":"855","issuingCountry":"UK"}]"#;
+
+/// barcode-lookup for a valid barcode that isn't in the database
+pub const BARCODE_NOT_FOUND: &str = r#"[{"error":"Barcode not found"}]"#;
+
+/// Any operation with an invalid API token
+pub const INVALID_TOKEN: &str = r#"[{"error":"Invalid token"}]"#;
+
+/// barcode-lookup for ISBN 1119578884
+pub const ISBN_LOOKUP: &str = r#"[{"ean":"9781119578888","name":"Linux Bible","categoryId":"15","categoryName":"Books and Magazines","googleCategoryId":"784","issuingCountry":""}]"#;
+
+/// Any operation after too many requests were sent (with HTTP status 429)
+pub const RATE_LIMITED: &str = r#"[{"error":"Too many requests"}]"#;
+
+/// product-search for "bananaboat", page 0
+pub const PRODUCT_SEARCH: &str = r#"{"page":"0","moreproducts":false,"totalproducts":2,"productlist":[{"ean":"0016494600224","name":"Bananaboat Song","categoryId":"45","categoryName":"Music","issuingCountry":"US"},{"ean":"5099750442227","name":"Michael Jackson: Thriller","categoryId":"45","categoryName":"Music","issuingCountry":"UK"}]}"#;
+
+/// product-search without any matches
+pub const PRODUCT_SEARCH_EMPTY: &str = r#"{"page":"0","moreproducts":false,"totalproducts":0,"productlist":[]}"#;
+
+/// issuing-country for 5099750442227
+pub const ISSUING_COUNTRY: &str = r#"[{"ean":"5099750442227","issuingCountry":"UK"}]"#;
+
+/// verify-checksum for a valid barcode
+pub const CHECKSUM_VALID: &str = r#"[{"ean":"5099750442227","valid":"1"}]"#;
+
+/// verify-checksum for an invalid barcode
+pub const CHECKSUM_INVALID: &str = r#"[{"ean":"5099750442228","valid":"0"}]"#;
+
+/// barcode-image for 5099750442227 (with a 1x1 pixel PNG to keep it short)
+pub const BARCODE_IMAGE: &str = r#"[{"ean":"5099750442227","barcode":"iVBORw0KGgoAAAANSUhEUgAAAAEAAAABAQAAAAA3bvkkAAAACklEQVR4nGNgAAAAAgABSK+kcQAAAABJRU5ErkJggg"}]"#;
+
+/// account-status
+pub const ACCOUNT_STATUS: &str = r#"{"id":"12345","requests":10,"requestlimit":100}"#;
+
+/// The web server's error page during maintenance
+pub const MAINTENANCE_PAGE: &str = "<html><head><title>503 Service Unavailable</title></head><body><h1>Service Unavailable</h1><p>We are down for maintenance.</p></body></html>";
+
+/// The sample response for an operation that found something (ignoring its parameters)
+pub fn body(op: &Operation) -> &'static str {
+    match op {
+        Operation::BarcodeLookup { .. } => BARCODE_LOOKUP,
+        Operation::IsbnLookup { .. } => ISBN_LOOKUP,
+        Operation::BarcodePrefixSearch { .. } | Operation::ProductSearch { .. } | Operation::CategorySearch { .. } => PRODUCT_SEARCH,
+        Operation::IssuingCountry { .. } => ISSUING_COUNTRY,
+        Operation::VerifyChecksum { .. } => CHECKSUM_VALID,
+        #[cfg(feature = "image")]
+        Operation::BarcodeImage { .. } => BARCODE_IMAGE,
+        Operation::AccountStatus => ACCOUNT_STATUS,
+    }
+}
+
+/// Parse a sample response for an operation
+///
+/// # Panics
+///
+/// If the body isn't a valid response to the operation, eg. an API error.
+pub fn parse(op: &Operation, body: &str) -> Response {
+    Response::parse(op, body).unwrap_or_else(|e| panic!("fixture for {} doesn't parse: {}", op.name(), e))
+}
+
+/// The product in [`BARCODE_LOOKUP`]
+pub fn product() -> Product {
+    match parse(&Operation::BarcodeLookup { ean: 5099750442227, language: None }, BARCODE_LOOKUP) {
+        Response::Product(Some(product)) => product,
+        _ => unreachable!("barcode-lookup returns a product"),
+    }
+}
+
+/// The products in [`PRODUCT_SEARCH`]
+pub fn product_list() -> Vec<Product> {
+    let op = Operation::ProductSearch { name: String::from("bananaboat"), language: None, page: crate::PageRequest::default() };
+    match parse(&op, PRODUCT_SEARCH) {
+        Response::ProductList(products) => products,
+        _ => unreachable!("product-search returns a product list"),
+    }
+}
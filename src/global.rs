@@ -0,0 +1,75 @@
+//! A shared client for quick scripts
+
+use std::sync::OnceLock;
+
+use crate::{EANSearch, EANSearchError, ErrorKind, PageRequest, Product};
+
+/// The environment variable with the API token of the [`default_client`]
+pub const TOKEN_VAR: &str = "EAN_SEARCH_API_TOKEN";
+
+/// The token variable of earlier versions, still read if [`TOKEN_VAR`] isn't set
+const LEGACY_TOKEN_VAR: &str = "EANSEARCH_TOKEN";
+
+static DEFAULT_CLIENT: OnceLock<EANSearch> = OnceLock::new();
+
+/// The shared client used by the free functions like [`lookup`], created on first use
+///
+/// The API token is read from [`TOKEN_VAR`] (`EAN_SEARCH_API_TOKEN`, or `EANSEARCH_TOKEN` as before),
+/// or else from the "default" [`Profile`](crate::Profile) (eg. `EANSEARCH_DEFAULT_TOKEN`).
+/// If neither is set, the call fails and the next one tries again.
+pub fn default_client() -> Result<&'static EANSearch, EANSearchError> {
+    if let Some(client) = DEFAULT_CLIENT.get() {
+        return Ok(client);
+    }
+    let client = new_client(|var| std::env::var(var).ok())?;
+    // if another thread got there first, its client is kept
+    Ok(DEFAULT_CLIENT.get_or_init(|| client))
+}
+
+fn new_client(var: impl Fn(&str) -> Option<String>) -> Result<EANSearch, EANSearchError> {
+    match token(var) {
+        Some(token) => EANSearch::builder(&token).build(),
+        None => EANSearch::profile("default").map_err(|e| {
+            ErrorKind::Config(format!("set {} or a default profile ({})", TOKEN_VAR, e)).into()
+        }),
+    }
+}
+
+fn token(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    [TOKEN_VAR, LEGACY_TOKEN_VAR].into_iter().find_map(|name| var(name).filter(|token| !token.is_empty()))
+}
+
+/// Look up a barcode with the [`default_client`]
+pub fn lookup(ean: u64) -> Result<Option<Product>, EANSearchError> {
+    default_client()?.barcode_lookup(ean, None)
+}
+
+/// Search for products by name with the [`default_client`] (first page of results)
+pub fn search(name: &str) -> Result<Vec<Product>, EANSearchError> {
+    default_client()?.product_search(name, None, PageRequest::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token() {
+        let env = |var: &str| match var {
+            "EAN_SEARCH_API_TOKEN" => Some(String::from("token")),
+            "EANSEARCH_TOKEN" => Some(String::from("legacy")),
+            _ => None,
+        };
+        assert_eq!(token(env).as_deref(), Some("token"));
+        let env = |var: &str| (var == "EANSEARCH_TOKEN").then(|| String::from("legacy"));
+        assert_eq!(token(env).as_deref(), Some("legacy"));
+        assert_eq!(token(|_| Some(String::new())), None);
+    }
+
+    #[test]
+    fn test_no_token() {
+        // without a token variable and a default profile (neither is set up for the tests)
+        let Err(e) = new_client(|_| None) else { panic!("created a client without a token") };
+        assert!(matches!(e.kind(), ErrorKind::Config(msg) if msg.starts_with("set EAN_SEARCH_API_TOKEN or a default profile")), "{}", e);
+    }
+}
@@ -0,0 +1,128 @@
+//! Throttling the request rate so the credits last until a given date
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Slows down requests when the credit burn rate would exhaust the quota before a deadline
+///
+/// The governor watches the `x-credits-remaining` header of the API responses and spaces
+/// requests so the remaining credits, at the observed cost per request, last until the deadline.
+/// As long as requests are slow enough anyway, they aren't delayed.
+/// Register it with [`EANSearch::with_governor`](crate::EANSearch::with_governor).
+#[derive(Debug)]
+pub struct CreditGovernor {
+    deadline: SystemTime,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Credits at the first response with the header, and the number of requests after it
+    first: Option<u32>,
+    requests: u32,
+    remaining: Option<u32>,
+    last_request: Option<Instant>,
+}
+
+impl CreditGovernor {
+    /// A governor that keeps credits available until the deadline (eg. the end of the billing period)
+    pub fn new(deadline: SystemTime) -> Self {
+        Self { deadline, state: Mutex::new(State::default()) }
+    }
+
+    /// The deadline the credits have to last until
+    pub fn deadline(&self) -> SystemTime {
+        self.deadline
+    }
+
+    /// The credits remaining according to the last response, if it had the header
+    pub fn credits_remaining(&self) -> Option<u32> {
+        self.state().remaining
+    }
+
+    /// Record the `x-credits-remaining` value of a response
+    pub fn observe(&self, credits_remaining: u32) {
+        let mut state = self.state();
+        match state.first {
+            // credits went up: the quota was renewed or extended, start over
+            Some(first) if credits_remaining > first => {
+                state.first = Some(credits_remaining);
+                state.requests = 0;
+            }
+            Some(_) => state.requests += 1,
+            None => state.first = Some(credits_remaining),
+        }
+        state.remaining = Some(credits_remaining);
+    }
+
+    /// The minimum time between two requests at the current burn rate, zero if there's no need to throttle
+    pub fn interval(&self) -> Duration {
+        self.interval_at(SystemTime::now())
+    }
+
+    fn interval_at(&self, now: SystemTime) -> Duration {
+        let state = self.state();
+        let (Some(first), Some(remaining)) = (state.first, state.remaining) else {
+            return Duration::ZERO;
+        };
+        let Ok(time_left) = self.deadline.duration_since(now) else {
+            return Duration::ZERO;
+        };
+        if remaining == 0 {
+            return time_left;
+        }
+        // average credits per request, assume 1 until some were used
+        let cost = if state.requests > 0 && first > remaining {
+            f64::from(first - remaining) / f64::from(state.requests)
+        } else {
+            1.0
+        };
+        // at most until the deadline, even if the next request costs more than what's left
+        time_left.mul_f64((cost / f64::from(remaining)).min(1.0))
+    }
+
+    /// Wait until the next request may be sent, then note it as sent
+    pub(crate) fn wait(&self) {
+        let interval = self.interval();
+        let last_request = self.state().last_request;
+        if let Some(wait) = last_request.and_then(|last| interval.checked_sub(last.elapsed())) {
+            std::thread::sleep(wait);
+        }
+        self.state().last_request = Some(Instant::now());
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval() {
+        let now = SystemTime::now();
+        let governor = CreditGovernor::new(now + Duration::from_secs(1000));
+        assert_eq!(governor.interval_at(now), Duration::ZERO);
+        governor.observe(100);
+        assert_eq!(governor.interval_at(now), Duration::from_secs(10));
+        // 2 credits per request: 50 requests left
+        governor.observe(98);
+        governor.observe(96);
+        assert_eq!(governor.credits_remaining(), Some(96));
+        assert_eq!(governor.interval_at(now + Duration::from_secs(40)), Duration::from_secs(20));
+        governor.observe(0);
+        assert_eq!(governor.interval_at(now), Duration::from_secs(1000));
+        // renewed quota
+        governor.observe(1000);
+        assert_eq!(governor.interval_at(now), Duration::from_secs(1));
+        // deadline passed
+        assert_eq!(governor.interval_at(now + Duration::from_secs(2000)), Duration::ZERO);
+        // more credits per request than left
+        let governor = CreditGovernor::new(now + Duration::from_secs(10_000_000_000));
+        governor.observe(u32::MAX);
+        governor.observe(1);
+        assert_eq!(governor.interval_at(now), Duration::from_secs(10_000_000_000));
+    }
+}
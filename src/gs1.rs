@@ -0,0 +1,439 @@
+//! GS1 structure of EAN codes: what the prefix is allocated for and the parts of a code
+
+use std::error::Error;
+use std::fmt;
+
+use crate::Ean;
+
+/// What the GS1 prefix of a code is allocated for, see [`Ean::allocation`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Allocation {
+    /// Codes assigned by a GS1 member organization, with its country code as the API returns it, eg. "DE"
+    MemberOrganization(&'static str),
+    /// Restricted circulation codes for use within a company or country, eg. variable weight items
+    RestrictedCirculation,
+    /// Coupons
+    Coupon,
+    /// Refund receipts, eg. from bottle deposit machines
+    RefundReceipt,
+    /// Serial publications (ISSN)
+    Issn,
+    /// Books (ISBN) and sheet music (ISMN)
+    Bookland,
+    /// Codes assigned by the GS1 Global Office, eg. EAN-8 codes in some countries
+    GlobalOffice,
+    /// Codes for demonstrations only
+    Demo,
+}
+
+/// Error for a code that is structurally implausible
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidStructure {
+    /// The GS1 prefix (3 digits) isn't allocated
+    UnallocatedPrefix(u16),
+    /// The company prefix length isn't possible for this code
+    CompanyPrefixLength(usize),
+    /// Codes with this allocation don't contain a company prefix
+    NoCompanyPrefix(Allocation),
+}
+
+impl fmt::Display for InvalidStructure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidStructure::UnallocatedPrefix(prefix) => write!(f, "GS1 prefix {:03} is not allocated", prefix),
+            InvalidStructure::CompanyPrefixLength(len) => write!(f, "invalid company prefix length {}", len),
+            InvalidStructure::NoCompanyPrefix(allocation) => write!(f, "{:?} codes have no company prefix", allocation),
+        }
+    }
+}
+
+impl Error for InvalidStructure {}
+
+/// The parts of a code, see [`Ean::decompose`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decomposition {
+    /// The packaging level indicator of a GTIN-14
+    pub indicator: Option<u8>,
+    /// The GS1 prefix (3 digits), eg. "509"
+    pub gs1_prefix: String,
+    /// The GS1 company prefix, starting with the GS1 prefix
+    pub company_prefix: String,
+    /// The item reference assigned by the company
+    pub item_reference: String,
+    /// The check digit
+    pub check_digit: u8,
+}
+
+/// What the value in a restricted circulation code is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MeasureKind {
+    /// A price, in the smallest currency unit (eg. cents)
+    Price,
+    /// A weight, usually in grams
+    Weight,
+}
+
+/// How a store or country encodes an item and a price or weight in restricted circulation codes,
+/// see [`Ean::decode_variable_measure`]
+///
+/// The 13 digit code is read as 2 digit prefix, item reference, optional price check digit,
+/// value and check digit. The schemes differ between countries and retailers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VariableMeasureLayout {
+    /// Number of digits of the item reference after the prefix
+    pub item_digits: usize,
+    /// Whether a price check digit precedes a 4 digit value
+    pub value_check_digit: bool,
+    /// What the value is
+    pub kind: MeasureKind,
+}
+
+impl VariableMeasureLayout {
+    /// The UPC scheme used in the US and Canada: 5 digit item, price check digit, 4 digit price in cents
+    pub const UPC_PRICE: VariableMeasureLayout = VariableMeasureLayout { item_digits: 5, value_check_digit: true, kind: MeasureKind::Price };
+    /// A common European scheme: 5 digit item and 5 digit price in cents
+    pub const ITEM5_PRICE5: VariableMeasureLayout = VariableMeasureLayout { item_digits: 5, value_check_digit: false, kind: MeasureKind::Price };
+    /// A common European scheme: 5 digit item and 5 digit weight in grams
+    pub const ITEM5_WEIGHT5: VariableMeasureLayout = VariableMeasureLayout { item_digits: 5, value_check_digit: false, kind: MeasureKind::Weight };
+}
+
+/// The fields of a variable measure code, see [`Ean::decode_variable_measure`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariableMeasure {
+    /// The first 2 digits of the 13 digit form, eg. "02" or "21"
+    pub prefix: String,
+    /// The item reference assigned by the store
+    pub item: String,
+    /// Price or weight
+    pub kind: MeasureKind,
+    /// The value in the smallest unit (eg. cents or grams)
+    pub value: u32,
+}
+
+/// Check digit for a 4 digit price in a variable measure code (GS1 weighting factors 2-, 2-, 3, 5-)
+fn price_check_digit(price: &[u8]) -> u8 {
+    const TWO_MINUS: [u8; 10] = [0, 2, 4, 6, 8, 9, 1, 3, 5, 7];
+    const THREE: [u8; 10] = [0, 3, 6, 9, 2, 5, 8, 1, 4, 7];
+    const FIVE_MINUS: [u8; 10] = [0, 5, 9, 4, 8, 3, 7, 2, 6, 1];
+    let sum = TWO_MINUS[price[0] as usize] + TWO_MINUS[price[1] as usize] + THREE[price[2] as usize] + FIVE_MINUS[price[3] as usize];
+    (sum * 3) % 10
+}
+
+use Allocation::*;
+
+// allocated GS1 prefixes (first 3 digits of a GTIN-13), from the GS1 prefix list
+const PREFIXES: &[(u16, u16, Allocation)] = &[
+    (0, 19, MemberOrganization("US")),
+    (20, 29, RestrictedCirculation),
+    (30, 39, MemberOrganization("US")),
+    (40, 49, RestrictedCirculation),
+    (50, 59, Coupon),
+    (60, 139, MemberOrganization("US")),
+    (200, 299, RestrictedCirculation),
+    (300, 379, MemberOrganization("FR")),
+    (380, 380, MemberOrganization("BG")),
+    (383, 383, MemberOrganization("SI")),
+    (385, 385, MemberOrganization("HR")),
+    (387, 387, MemberOrganization("BA")),
+    (389, 389, MemberOrganization("ME")),
+    (390, 390, MemberOrganization("XK")),
+    (400, 440, MemberOrganization("DE")),
+    (450, 459, MemberOrganization("JP")),
+    (460, 469, MemberOrganization("RU")),
+    (470, 470, MemberOrganization("KG")),
+    (471, 471, MemberOrganization("TW")),
+    (474, 474, MemberOrganization("EE")),
+    (475, 475, MemberOrganization("LV")),
+    (476, 476, MemberOrganization("AZ")),
+    (477, 477, MemberOrganization("LT")),
+    (478, 478, MemberOrganization("UZ")),
+    (479, 479, MemberOrganization("LK")),
+    (480, 480, MemberOrganization("PH")),
+    (481, 481, MemberOrganization("BY")),
+    (482, 482, MemberOrganization("UA")),
+    (483, 483, MemberOrganization("TM")),
+    (484, 484, MemberOrganization("MD")),
+    (485, 485, MemberOrganization("AM")),
+    (486, 486, MemberOrganization("GE")),
+    (487, 487, MemberOrganization("KZ")),
+    (488, 488, MemberOrganization("TJ")),
+    (489, 489, MemberOrganization("HK")),
+    (490, 499, MemberOrganization("JP")),
+    (500, 509, MemberOrganization("UK")),
+    (520, 521, MemberOrganization("GR")),
+    (528, 528, MemberOrganization("LB")),
+    (529, 529, MemberOrganization("CY")),
+    (530, 530, MemberOrganization("AL")),
+    (531, 531, MemberOrganization("MK")),
+    (535, 535, MemberOrganization("MT")),
+    (539, 539, MemberOrganization("IE")),
+    (540, 549, MemberOrganization("BE")),
+    (560, 560, MemberOrganization("PT")),
+    (569, 569, MemberOrganization("IS")),
+    (570, 579, MemberOrganization("DK")),
+    (590, 590, MemberOrganization("PL")),
+    (594, 594, MemberOrganization("RO")),
+    (599, 599, MemberOrganization("HU")),
+    (600, 601, MemberOrganization("ZA")),
+    (603, 603, MemberOrganization("GH")),
+    (604, 604, MemberOrganization("SN")),
+    (608, 608, MemberOrganization("BH")),
+    (609, 609, MemberOrganization("MU")),
+    (611, 611, MemberOrganization("MA")),
+    (613, 613, MemberOrganization("DZ")),
+    (615, 615, MemberOrganization("NG")),
+    (616, 616, MemberOrganization("KE")),
+    (617, 617, MemberOrganization("CM")),
+    (618, 618, MemberOrganization("CI")),
+    (619, 619, MemberOrganization("TN")),
+    (620, 620, MemberOrganization("TZ")),
+    (621, 621, MemberOrganization("SY")),
+    (622, 622, MemberOrganization("EG")),
+    (623, 623, MemberOrganization("BN")),
+    (624, 624, MemberOrganization("LY")),
+    (625, 625, MemberOrganization("JO")),
+    (626, 626, MemberOrganization("IR")),
+    (627, 627, MemberOrganization("KW")),
+    (628, 628, MemberOrganization("SA")),
+    (629, 629, MemberOrganization("AE")),
+    (630, 630, MemberOrganization("QA")),
+    (631, 631, MemberOrganization("NA")),
+    (640, 649, MemberOrganization("FI")),
+    (680, 681, MemberOrganization("CN")),
+    (690, 699, MemberOrganization("CN")),
+    (700, 709, MemberOrganization("NO")),
+    (729, 729, MemberOrganization("IL")),
+    (730, 739, MemberOrganization("SE")),
+    (740, 740, MemberOrganization("GT")),
+    (741, 741, MemberOrganization("SV")),
+    (742, 742, MemberOrganization("HN")),
+    (743, 743, MemberOrganization("NI")),
+    (744, 744, MemberOrganization("CR")),
+    (745, 745, MemberOrganization("PA")),
+    (746, 746, MemberOrganization("DO")),
+    (750, 750, MemberOrganization("MX")),
+    (754, 755, MemberOrganization("CA")),
+    (759, 759, MemberOrganization("VE")),
+    (760, 769, MemberOrganization("CH")),
+    (770, 771, MemberOrganization("CO")),
+    (773, 773, MemberOrganization("UY")),
+    (775, 775, MemberOrganization("PE")),
+    (777, 777, MemberOrganization("BO")),
+    (778, 779, MemberOrganization("AR")),
+    (780, 780, MemberOrganization("CL")),
+    (784, 784, MemberOrganization("PY")),
+    (786, 786, MemberOrganization("EC")),
+    (789, 790, MemberOrganization("BR")),
+    (800, 839, MemberOrganization("IT")),
+    (840, 849, MemberOrganization("ES")),
+    (850, 850, MemberOrganization("CU")),
+    (858, 858, MemberOrganization("SK")),
+    (859, 859, MemberOrganization("CZ")),
+    (860, 860, MemberOrganization("RS")),
+    (865, 865, MemberOrganization("MN")),
+    (867, 867, MemberOrganization("KP")),
+    (868, 869, MemberOrganization("TR")),
+    (870, 879, MemberOrganization("NL")),
+    (880, 880, MemberOrganization("KR")),
+    (883, 883, MemberOrganization("MM")),
+    (884, 884, MemberOrganization("KH")),
+    (885, 885, MemberOrganization("TH")),
+    (888, 888, MemberOrganization("SG")),
+    (890, 890, MemberOrganization("IN")),
+    (893, 893, MemberOrganization("VN")),
+    (896, 896, MemberOrganization("PK")),
+    (899, 899, MemberOrganization("ID")),
+    (900, 919, MemberOrganization("AT")),
+    (930, 939, MemberOrganization("AU")),
+    (940, 949, MemberOrganization("NZ")),
+    (950, 951, GlobalOffice),
+    (952, 952, Demo),
+    (955, 955, MemberOrganization("MY")),
+    (958, 958, MemberOrganization("MO")),
+    (960, 969, GlobalOffice),
+    (977, 977, Issn),
+    (978, 979, Bookland),
+    (980, 980, RefundReceipt),
+    (981, 984, Coupon),
+    (990, 999, Coupon),
+];
+
+/// All GS1 prefixes of member organizations, optionally only those of one country
+#[cfg(feature = "random")]
+pub(crate) fn member_prefixes(country: Option<&str>) -> impl Iterator<Item = u16> + '_ {
+    PREFIXES.iter()
+        .filter(move |(_, _, allocation)| match allocation {
+            MemberOrganization(code) => country.map_or(true, |c| c.eq_ignore_ascii_case(code)),
+            _ => false,
+        })
+        .flat_map(|(first, last, _)| *first..=*last)
+}
+
+impl Ean {
+    /// The 13 digit form of the code without packaging indicator (None for EAN-8)
+    pub(crate) fn gtin13(&self) -> Option<String> {
+        match self.digits() {
+            8 => None,
+            14 => Some(self.to_string()[1..].to_string()),
+            _ => Some(format!("{:0>13}", self.code())),
+        }
+    }
+
+    /// The GS1 prefix: the first 3 digits of the 13 digit form (or of an EAN-8 code)
+    pub fn gs1_prefix(&self) -> u16 {
+        let digits = self.gtin13().unwrap_or_else(|| self.to_string());
+        digits[..3].parse().unwrap_or_default()
+    }
+
+    /// What the GS1 prefix of this code is allocated for (an error if it isn't allocated)
+    pub fn allocation(&self) -> Result<Allocation, InvalidStructure> {
+        let prefix = self.gs1_prefix();
+        if self.digits() == 8 {
+            // EAN-8 codes starting with 0 or 2 are for restricted circulation
+            match prefix {
+                0..=99 | 200..=299 => return Ok(RestrictedCirculation),
+                977..=999 => return Err(InvalidStructure::UnallocatedPrefix(prefix)),
+                _ => (),
+            }
+        }
+        PREFIXES.iter()
+            .find(|(first, last, _)| (*first..=*last).contains(&prefix))
+            .map(|(_, _, allocation)| *allocation)
+            .ok_or(InvalidStructure::UnallocatedPrefix(prefix))
+    }
+
+    /// Whether this is a restricted circulation code (starting with 02, 04 or 2), eg. a store-internal
+    /// or variable weight code, which will never be found in the global database
+    pub fn is_restricted_circulation(&self) -> bool {
+        self.allocation() == Ok(RestrictedCirculation)
+    }
+
+    /// Whether this is a coupon code (prefixes 05, 981-984 and 99, or UPC-A codes starting with 5),
+    /// which identifies a coupon instead of a product
+    pub fn is_coupon(&self) -> bool {
+        self.allocation() == Ok(Coupon)
+    }
+
+    /// Decode the item and price or weight of a restricted circulation code with the layout
+    /// used by the store (None if this isn't a restricted circulation code, it doesn't fit the
+    /// layout or the price check digit is wrong)
+    pub fn decode_variable_measure(&self, layout: VariableMeasureLayout) -> Option<VariableMeasure> {
+        if !self.is_restricted_circulation() {
+            return None;
+        }
+        let digits = self.gtin13()?;
+        // prefix, item, (price check digit), value, check digit
+        let value_start = 2 + layout.item_digits + usize::from(layout.value_check_digit);
+        let value = digits.get(value_start..12).filter(|v| !v.is_empty())?;
+        if layout.value_check_digit {
+            let value_digits : Vec<u8> = value.bytes().map(|b| b - b'0').collect();
+            if value_digits.len() != 4 || price_check_digit(&value_digits) != digits.as_bytes()[value_start - 1] - b'0' {
+                return None;
+            }
+        }
+        Some(VariableMeasure {
+            prefix: digits[..2].to_string(),
+            item: digits[2..2 + layout.item_digits].to_string(),
+            kind: layout.kind,
+            value: value.parse().ok()?,
+        })
+    }
+
+    /// Split the code into GS1 prefix, company prefix, item reference and check digit
+    ///
+    /// The length of the company prefix can't be derived from the code itself, it has to be looked up
+    /// in the member organization's register: it counts the digits of the 13 digit form, starting with
+    /// the GS1 prefix, and is 6 to 12 digits (7 to 12 for US/Canada codes starting with 0).
+    /// EAN-8 codes have no company prefix, their 3 digit GS1-8 prefix is returned with length 3.
+    pub fn decompose(&self, company_prefix_len: usize) -> Result<Decomposition, InvalidStructure> {
+        let allocation = self.allocation()?;
+        if !matches!(allocation, MemberOrganization(_) | GlobalOffice) {
+            return Err(InvalidStructure::NoCompanyPrefix(allocation));
+        }
+        let check_digit = (self.code() % 10) as u8;
+        let (indicator, digits, valid_len) = match self.gtin13() {
+            None => (None, self.to_string(), 3..=3),
+            Some(digits) => {
+                let indicator = if self.digits() == 14 { Some((self.code() / 10u64.pow(13)) as u8) } else { None };
+                let min = if digits.starts_with('0') { 7 } else { 6 };
+                (indicator, digits, min..=12)
+            }
+        };
+        if !valid_len.contains(&company_prefix_len) {
+            return Err(InvalidStructure::CompanyPrefixLength(company_prefix_len));
+        }
+        Ok(Decomposition {
+            indicator,
+            gs1_prefix: digits[..3].to_string(),
+            company_prefix: digits[..company_prefix_len].to_string(),
+            item_reference: digits[company_prefix_len..digits.len() - 1].to_string(),
+            check_digit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ean(code: &str) -> Ean {
+        Ean::parse(code).unwrap()
+    }
+
+    #[test]
+    fn test_allocation() {
+        assert_eq!(ean("5099750442227").allocation(), Ok(MemberOrganization("UK")));
+        assert_eq!(ean("4006381333931").allocation(), Ok(MemberOrganization("DE")));
+        assert_eq!(ean("036000291452").allocation(), Ok(MemberOrganization("US"))); // UPC-A
+        assert_eq!(ean("05099750442227").allocation(), Ok(MemberOrganization("UK"))); // GTIN-14
+        assert_eq!(ean("2001234000000").allocation(), Ok(RestrictedCirculation));
+        assert_eq!(ean("9780306406157").allocation(), Ok(Bookland));
+        assert_eq!(ean("9771234567003").allocation(), Ok(Issn));
+        assert_eq!(ean("1400000000007").allocation(), Err(InvalidStructure::UnallocatedPrefix(140)));
+        assert_eq!(ean("96385074").allocation(), Ok(GlobalOffice));
+        assert_eq!(ean("20123451").allocation(), Ok(RestrictedCirculation));
+        assert!(PREFIXES.windows(2).all(|w| w[0].1 < w[1].0)); // sorted, not overlapping
+    }
+
+    #[test]
+    fn test_coupon() {
+        assert!(ean("512345678900").is_coupon()); // UPC-A, number system 5
+        assert!(ean("9912345678909").is_coupon());
+        assert!(ean("9812345678902").is_coupon());
+        assert!(!ean("9802345678903").is_coupon()); // refund receipt
+        assert!(!ean("5099750442227").is_coupon());
+    }
+
+    #[test]
+    fn test_decompose() {
+        let d = ean("5099750442227").decompose(7).unwrap();
+        assert_eq!(d, Decomposition {
+            indicator: None, gs1_prefix: String::from("509"), company_prefix: String::from("5099750"),
+            item_reference: String::from("44222"), check_digit: 7,
+        });
+        let d = ean("15099750442224").decompose(9).unwrap();
+        assert_eq!((d.indicator, d.company_prefix.as_str(), d.item_reference.as_str()), (Some(1), "509975044", "222"));
+        let d = ean("96385074").decompose(3).unwrap();
+        assert_eq!((d.gs1_prefix.as_str(), d.item_reference.as_str(), d.check_digit), ("963", "8507", 4));
+        assert_eq!(ean("036000291452").decompose(6), Err(InvalidStructure::CompanyPrefixLength(6)));
+        assert_eq!(ean("5099750442227").decompose(13), Err(InvalidStructure::CompanyPrefixLength(13)));
+        assert_eq!(ean("9780306406157").decompose(7), Err(InvalidStructure::NoCompanyPrefix(Bookland)));
+    }
+
+    #[test]
+    fn test_variable_measure() {
+        assert!(ean("2001234000000").is_restricted_circulation());
+        assert!(ean("212345928752").is_restricted_circulation()); // UPC-A, number system 2
+        assert!(!ean("5099750442227").is_restricted_circulation());
+        // item 12345, price check digit 9, price 28.75
+        let vm = ean("0212345928752").decode_variable_measure(VariableMeasureLayout::UPC_PRICE).unwrap();
+        assert_eq!(vm, VariableMeasure { prefix: String::from("02"), item: String::from("12345"), kind: MeasureKind::Price, value: 2875 });
+        assert_eq!(ean("0212345828755").decode_variable_measure(VariableMeasureLayout::UPC_PRICE), None); // wrong price check digit
+        let vm = ean("2812345012345").decode_variable_measure(VariableMeasureLayout::ITEM5_WEIGHT5).unwrap();
+        assert_eq!((vm.item.as_str(), vm.kind, vm.value), ("12345", MeasureKind::Weight, 1234));
+        assert_eq!(ean("5099750442227").decode_variable_measure(VariableMeasureLayout::ITEM5_PRICE5), None);
+        let too_long = VariableMeasureLayout { item_digits: 10, value_check_digit: false, kind: MeasureKind::Price };
+        assert_eq!(ean("2812345012345").decode_variable_measure(too_long), None);
+    }
+}
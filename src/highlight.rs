@@ -0,0 +1,95 @@
+//! Highlight the search keywords in product names, eg. on a storefront search page
+
+use std::ops::Range;
+
+use crate::Product;
+
+/// A text with the byte ranges that matched the search keywords, see [`highlight`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Highlight<'a> {
+    /// The highlighted text
+    pub text: &'a str,
+    /// The matches as sorted, non-overlapping byte ranges into `text`
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl Highlight<'_> {
+    /// The text with each match wrapped in `start` and `end`, eg. `<b>` and `</b>`
+    ///
+    /// The text isn't escaped, so HTML output needs the product name to be escaped first.
+    pub fn marked(&self, start: &str, end: &str) -> String {
+        let mut marked = String::with_capacity(self.text.len() + self.ranges.len() * (start.len() + end.len()));
+        let mut pos = 0;
+        for range in &self.ranges {
+            marked.push_str(&self.text[pos..range.start]);
+            marked.push_str(start);
+            marked.push_str(&self.text[range.clone()]);
+            marked.push_str(end);
+            pos = range.end;
+        }
+        marked.push_str(&self.text[pos..]);
+        marked
+    }
+}
+
+/// Find the search keywords (separated by whitespace) in the name of a product
+///
+/// Keywords are matched case insensitively anywhere in the name, like the API does.
+pub fn highlight<'a>(keywords: &str, product: &'a Product) -> Highlight<'a> {
+    highlight_text(keywords, &product.name)
+}
+
+/// Find the search keywords (separated by whitespace) in any text
+pub fn highlight_text<'a>(keywords: &str, text: &'a str) -> Highlight<'a> {
+    let mut ranges : Vec<Range<usize>> = keywords.split_whitespace()
+        .flat_map(|keyword| find_all(text, keyword))
+        .collect();
+    ranges.sort_by_key(|r| r.start);
+    // merge overlapping and adjacent matches
+    let mut merged : Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    Highlight { text, ranges: merged }
+}
+
+/// All case insensitive occurrences of keyword in text
+fn find_all(text: &str, keyword: &str) -> Vec<Range<usize>> {
+    text.char_indices()
+        .filter_map(|(start, _)| match_len(&text[start..], keyword).map(|len| start..start + len))
+        .collect()
+}
+
+/// The length in bytes of the prefix of text that matches keyword
+fn match_len(text: &str, keyword: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    let mut len = 0;
+    for k in keyword.chars() {
+        let (i, c) = chars.next()?;
+        if !c.to_lowercase().eq(k.to_lowercase()) {
+            return None;
+        }
+        len = i + c.len_utf8();
+    }
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let h = highlight_text("thriller JACKSON", "Michael Jackson: Thriller");
+        assert_eq!(h.ranges, vec![8..15, 17..25]);
+        assert_eq!(h.marked("<b>", "</b>"), "Michael <b>Jackson</b>: <b>Thriller</b>");
+        // overlapping keywords are merged
+        assert_eq!(highlight_text("banana anab", "Bananaboat").ranges, vec![0..7]);
+        assert_eq!(highlight_text("müller", "MÜLLER Milch").marked("*", "*"), "*MÜLLER* Milch");
+        assert!(highlight_text("", "Bananaboat").ranges.is_empty());
+        assert!(highlight_text("xyz", "Bananaboat").ranges.is_empty());
+    }
+}
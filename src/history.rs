@@ -0,0 +1,137 @@
+//! Timestamped snapshots of lookups, to tell what the database said about a product at some time
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Product;
+
+/// The result of a lookup at some time (`product` is None if the EAN wasn't found)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub ean: u64,
+    pub time: SystemTime,
+    pub product: Option<Product>,
+}
+
+/// Keeps the snapshots of lookups, see [`EANSearch::barcode_lookup_recorded`](crate::EANSearch::barcode_lookup_recorded)
+pub trait SnapshotStore {
+    /// Add a snapshot
+    fn record(&mut self, snapshot: Snapshot) -> io::Result<()>;
+    /// All snapshots of an EAN, oldest first
+    fn history(&self, ean: u64) -> io::Result<Vec<Snapshot>>;
+
+    /// The latest snapshot of an EAN taken at or before the time (None if there is none)
+    fn at(&self, ean: u64, time: SystemTime) -> io::Result<Option<Snapshot>> {
+        Ok(self.history(ean)?.into_iter().rev().find(|s| s.time <= time))
+    }
+}
+
+/// Snapshots kept in memory
+#[derive(Clone, Debug, Default)]
+pub struct MemorySnapshots {
+    snapshots: Vec<Snapshot>,
+}
+
+impl MemorySnapshots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SnapshotStore for MemorySnapshots {
+    fn record(&mut self, snapshot: Snapshot) -> io::Result<()> {
+        self.snapshots.push(snapshot);
+        Ok(())
+    }
+
+    fn history(&self, ean: u64) -> io::Result<Vec<Snapshot>> {
+        let mut history : Vec<Snapshot> = self.snapshots.iter().filter(|s| s.ean == ean).cloned().collect();
+        history.sort_by_key(|s| s.time);
+        Ok(history)
+    }
+}
+
+/// Snapshots appended to a file, one JSON object per line
+#[derive(Clone, Debug)]
+pub struct FileSnapshots {
+    path: PathBuf,
+}
+
+impl FileSnapshots {
+    /// Use this file (created with the first snapshot)
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SnapshotStore for FileSnapshots {
+    fn record(&mut self, snapshot: Snapshot) -> io::Result<()> {
+        let mut line = serde_json::to_string(&snapshot)?;
+        line.push('\n');
+        // a single write, so concurrent writers don't interleave lines
+        fs::OpenOptions::new().create(true).append(true).open(&self.path)?.write_all(line.as_bytes())
+    }
+
+    fn history(&self, ean: u64) -> io::Result<Vec<Snapshot>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut history = Vec::new();
+        for line in io::BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let snapshot : Snapshot = serde_json::from_str(&line)?;
+            if snapshot.ean == ean {
+                history.push(snapshot);
+            }
+        }
+        history.sort_by_key(|s| s.time);
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::time::Duration;
+
+    fn check_store(store: &mut impl SnapshotStore) {
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut renamed = fixtures::product();
+        renamed.name = String::from("Thriller");
+        store.record(Snapshot { ean: 5099750442227, time: t0, product: None }).unwrap();
+        store.record(Snapshot { ean: 5099750442227, time: t0 + Duration::from_secs(100), product: Some(fixtures::product()) }).unwrap();
+        store.record(Snapshot { ean: 4006381333931, time: t0 + Duration::from_secs(150), product: None }).unwrap();
+        store.record(Snapshot { ean: 5099750442227, time: t0 + Duration::from_secs(200), product: Some(renamed) }).unwrap();
+        assert_eq!(store.history(5099750442227).unwrap().len(), 3);
+        assert!(store.history(1).unwrap().is_empty());
+        assert!(store.at(5099750442227, t0 - Duration::from_secs(1)).unwrap().is_none());
+        assert!(store.at(5099750442227, t0 + Duration::from_secs(50)).unwrap().unwrap().product.is_none());
+        let then = store.at(5099750442227, t0 + Duration::from_secs(199)).unwrap().unwrap();
+        assert_eq!(then.product.unwrap().name, "Michael Jackson: Thriller");
+        let now = store.at(5099750442227, SystemTime::now()).unwrap().unwrap();
+        assert_eq!(now.product.unwrap().name, "Thriller");
+    }
+
+    #[test]
+    fn test_memory_snapshots() {
+        check_store(&mut MemorySnapshots::new());
+    }
+
+    #[test]
+    fn test_file_snapshots() {
+        let path = std::env::temp_dir().join(format!("eansearch-snapshots-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        check_store(&mut FileSnapshots::new(&path));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+//! Iterate over the products of a list operation across all pages
+
+use crate::{DefaultTransport, EANSearch, EANSearchError, ErrorKind, Operation, Product, Transport};
+
+/// Iterator over the products of a list operation, see [`EANSearch::iter_products`]
+///
+/// Pages are fetched lazily when the products of the previous page have been consumed.
+/// The iteration ends after the last page (as the API reports it, or with the first empty page)
+/// or after the first error.
+pub struct ProductIter<'a, T: Transport = DefaultTransport> {
+    eansearch: &'a EANSearch<T>,
+    next_op: Option<Operation>,
+    products: std::vec::IntoIter<Product>,
+}
+
+impl<'a, T: Transport> ProductIter<'a, T> {
+    pub(crate) fn new(eansearch: &'a EANSearch<T>, op: Operation) -> Self {
+        Self { eansearch, next_op: Some(op), products: Vec::new().into_iter() }
+    }
+}
+
+impl<T: Transport> Iterator for ProductIter<'_, T> {
+    type Item = Result<Product, EANSearchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(product) = self.products.next() {
+                return Some(Ok(product));
+            }
+            let op = self.next_op.take()?;
+            if op.page().is_none() {
+                let msg = format!("{} is not a list operation", op.name());
+                return Some(Err(ErrorKind::InvalidArgument(msg).into()));
+            }
+            match self.eansearch.list_page(&op) {
+                Ok((products, more)) => {
+                    if products.is_empty() {
+                        return None;
+                    }
+                    // don't spend a credit on the empty page after the last one
+                    self.next_op = if more == Some(false) { None } else { op.next_page() };
+                    self.products = products.into_iter();
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
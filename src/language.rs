@@ -0,0 +1,66 @@
+//! The languages product names can be requested in
+
+/// A language supported by the API, for the `language` parameter of the lookup and search methods
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Language {
+    /// The code to pass as `language` parameter, eg. 1 for English
+    pub code: i8,
+    /// ISO 639-1 code, eg. "en" (empty for [`Language::ANY`])
+    pub iso: &'static str,
+    /// English name of the language, eg. "English"
+    pub name: &'static str,
+}
+
+impl Language {
+    /// Don't restrict the product names to a language (default for the product and category search)
+    pub const ANY: Language = Language { code: 99, iso: "", name: "Any language" };
+
+    /// The language with this API code, if it's supported
+    pub fn from_code(code: i8) -> Option<Language> {
+        LANGUAGES.iter().find(|l| l.code == code).copied()
+    }
+
+    /// The language with this ISO 639-1 code (case insensitive), if it's supported
+    pub fn from_iso(iso: &str) -> Option<Language> {
+        LANGUAGES.iter().find(|l| !l.iso.is_empty() && l.iso.eq_ignore_ascii_case(iso)).copied()
+    }
+}
+
+// the API has no endpoint to query the languages, this is the table from the API documentation
+const LANGUAGES: &[Language] = &[
+    Language { code: 1, iso: "en", name: "English" },
+    Language { code: 2, iso: "da", name: "Danish" },
+    Language { code: 3, iso: "de", name: "German" },
+    Language { code: 4, iso: "es", name: "Spanish" },
+    Language { code: 5, iso: "fi", name: "Finnish" },
+    Language { code: 6, iso: "fr", name: "French" },
+    Language { code: 8, iso: "it", name: "Italian" },
+    Language { code: 10, iso: "nl", name: "Dutch" },
+    Language { code: 11, iso: "no", name: "Norwegian" },
+    Language { code: 12, iso: "pl", name: "Polish" },
+    Language { code: 13, iso: "pt", name: "Portuguese" },
+    Language { code: 15, iso: "sv", name: "Swedish" },
+    Language::ANY,
+];
+
+/// All languages the API supports, eg. to build a language selector
+pub fn supported_languages() -> &'static [Language] {
+    LANGUAGES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_languages() {
+        assert_eq!(Language::from_code(1).unwrap().name, "English");
+        assert_eq!(Language::from_code(99), Some(Language::ANY));
+        assert_eq!(Language::from_code(7), None);
+        assert_eq!(Language::from_iso("DE").unwrap().code, 3);
+        assert_eq!(Language::from_iso(""), None);
+        let mut codes : Vec<i8> = supported_languages().iter().map(|l| l.code).collect();
+        codes.dedup();
+        assert_eq!(codes.len(), supported_languages().len());
+    }
+}
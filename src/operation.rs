@@ -0,0 +1,267 @@
+//! API operations as values, to be run with [`EANSearch::execute`](crate::EANSearch::execute)
+
+use crate::{EANSearchError, PageRequest, Product};
+
+/// An API operation together with its parameters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Search for a product by EAN barcode
+    BarcodeLookup { ean: u64, language: Option<i8> },
+    /// Lookup a book by ISBN-10 or ISBN-13 code
+    IsbnLookup { isbn: u64 },
+    /// Search for all products with an EAN barcode starting with this prefix
+    BarcodePrefixSearch { prefix: u64, language: Option<i8>, page: PageRequest },
+    /// Search for all products matching all keywords in name
+    ProductSearch { name: String, language: Option<i8>, page: PageRequest },
+    /// Search for all products in a product category, optionally restricted by keywords in name
+    CategorySearch { category: i32, name: Option<String>, language: Option<i8>, page: PageRequest },
+    /// Query the country that issued an EAN barcode
+    IssuingCountry { ean: u64 },
+    /// Verify if the provided number is a valid EAN barcode
+    VerifyChecksum { ean: u64 },
+    /// Get a PNG image of the EAN barcode
+    #[cfg(feature = "image")]
+    BarcodeImage { ean: u64, symbology: Symbology, width: Option<i32>, height: Option<i32> },
+    /// Check how many requests are still available for your account
+    AccountStatus,
+}
+
+impl Operation {
+    /// The operation name used by the API, eg. "barcode-lookup"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::BarcodeLookup { .. } | Operation::IsbnLookup { .. } => "barcode-lookup",
+            Operation::BarcodePrefixSearch { .. } => "barcode-prefix-search",
+            Operation::ProductSearch { .. } => "product-search",
+            Operation::CategorySearch { .. } => "category-search",
+            Operation::IssuingCountry { .. } => "issuing-country",
+            Operation::VerifyChecksum { .. } => "verify-checksum",
+            #[cfg(feature = "image")]
+            Operation::BarcodeImage { .. } => "barcode-image",
+            Operation::AccountStatus => "account-status",
+        }
+    }
+
+    /// The number of credits the operation uses according to the current pricing model
+    ///
+    /// Every request uses one credit, also for a barcode that isn't found or an empty page.
+    /// Checking the account status is free.
+    pub fn credit_cost(&self) -> u32 {
+        match self {
+            Operation::AccountStatus => 0,
+            _ => 1,
+        }
+    }
+
+    /// The requested page, for the list operations (None for all other operations)
+    pub fn page(&self) -> Option<PageRequest> {
+        match self {
+            Operation::BarcodePrefixSearch { page, .. }
+            | Operation::ProductSearch { page, .. }
+            | Operation::CategorySearch { page, .. } => Some(*page),
+            _ => None,
+        }
+    }
+
+    /// The same list operation for the following page (None for all other operations)
+    pub fn next_page(&self) -> Option<Operation> {
+        let mut op = self.clone();
+        match &mut op {
+            Operation::BarcodePrefixSearch { page, .. }
+            | Operation::ProductSearch { page, .. }
+            | Operation::CategorySearch { page, .. } => *page = page.next()?,
+            _ => return None,
+        }
+        Some(op)
+    }
+
+    /// The same operation with the language set, if it has a language parameter that isn't set (None otherwise)
+    pub(crate) fn with_default_language(&self, default: i8) -> Option<Operation> {
+        let mut op = self.clone();
+        match &mut op {
+            Operation::BarcodeLookup { language, .. }
+            | Operation::BarcodePrefixSearch { language, .. }
+            | Operation::ProductSearch { language, .. }
+            | Operation::CategorySearch { language, .. } if language.is_none() => *language = Some(default),
+            _ => return None,
+        }
+        Some(op)
+    }
+
+    /// The parameters sent to the API for this operation (defaults filled in)
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Operation::BarcodeLookup { ean, language } => vec![
+                ("ean", ean.to_string()),
+                ("language", language.unwrap_or(1).to_string()),
+            ],
+            Operation::IsbnLookup { isbn } => vec![("isbn", isbn.to_string())],
+            Operation::BarcodePrefixSearch { prefix, language, page } => vec![
+                ("prefix", prefix.to_string()),
+                ("page", page.page().to_string()),
+                ("language", language.unwrap_or(1).to_string()),
+            ],
+            Operation::ProductSearch { name, language, page } => vec![
+                ("name", name.clone()),
+                ("language", language.unwrap_or(99).to_string()),
+                ("page", page.page().to_string()),
+            ],
+            Operation::CategorySearch { category, name, language, page } => {
+                let mut params = vec![("category", category.to_string())];
+                if let Some(name) = name {
+                    params.push(("name", name.clone()));
+                }
+                params.push(("language", language.unwrap_or(99).to_string()));
+                params.push(("page", page.page().to_string()));
+                params
+            }
+            Operation::IssuingCountry { ean } | Operation::VerifyChecksum { ean } => vec![("ean", ean.to_string())],
+            #[cfg(feature = "image")]
+            Operation::BarcodeImage { ean, symbology, width, height } => vec![
+                ("ean", symbology.format(*ean)),
+                ("width", width.unwrap_or(102).to_string()),
+                ("height", height.unwrap_or(50).to_string()),
+            ],
+            Operation::AccountStatus => vec![],
+        }
+    }
+
+    /// The query string for this operation (with percent-encoded values), to be appended to the base URL
+    pub(crate) fn query(&self) -> String {
+        let mut query = String::from("&op=") + self.name();
+        for (name, value) in self.params() {
+            query = query + "&" + name + "=" + &crate::proto::encode(&value);
+        }
+        query
+    }
+}
+
+/// The kind of barcode symbol to render, see [`Operation::BarcodeImage`]
+#[cfg(feature = "image")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Symbology {
+    /// 13 digit EAN-13 (UPC-A codes are EAN-13 codes with a leading 0)
+    #[default]
+    Ean13,
+    /// 8 digit EAN-8, eg. for small packages and shelf-edge labels
+    Ean8,
+}
+
+#[cfg(feature = "image")]
+impl Symbology {
+    /// Number of digits of a code in this symbology
+    pub fn digits(&self) -> usize {
+        match self {
+            Symbology::Ean13 => 13,
+            Symbology::Ean8 => 8,
+        }
+    }
+
+    /// The symbology of a code by its number of digits: EAN-8 up to 8 digits, otherwise EAN-13
+    pub fn of(ean: u64) -> Symbology {
+        if Symbology::Ean8.fits(ean) { Symbology::Ean8 } else { Symbology::Ean13 }
+    }
+
+    /// Whether the code fits into this symbology
+    pub fn fits(&self, ean: u64) -> bool {
+        ean < 10u64.pow(self.digits() as u32)
+    }
+
+    /// The code with leading zeros, as sent to the API
+    fn format(&self, ean: u64) -> String {
+        format!("{:0>width$}", ean, width = self.digits())
+    }
+}
+
+/// The result of an [`Operation`]
+#[derive(Clone, Debug)]
+pub enum Response {
+    /// Result of a barcode or ISBN lookup (None if the barcode wasn't found)
+    Product(Option<Product>),
+    /// Result of a prefix, product or category search
+    ProductList(Vec<Product>),
+    /// The country that issued the barcode
+    IssuingCountry(String),
+    /// Whether the checksum of the barcode is valid
+    ChecksumValid(bool),
+    /// PNG image of the barcode
+    #[cfg(feature = "image")]
+    BarcodeImage(Vec<u8>),
+    /// Number of requests still available for the account
+    AccountStatus(u32),
+}
+
+impl Response {
+    /// Parse the response body the API returned for an operation
+    ///
+    /// Malformed bodies result in an error, parsing never panics.
+    pub fn parse(op: &Operation, body: &str) -> Result<Response, EANSearchError> {
+        Ok(crate::parse::response(op, body)?)
+    }
+
+    /// Parse a response body like [`parse`](Self::parse), but fail on fields the parser doesn't know
+    /// ([`ErrorKind::UnknownFields`](crate::ErrorKind::UnknownFields)), to detect API changes
+    pub fn parse_strict(op: &Operation, body: &str) -> Result<Response, EANSearchError> {
+        let response = crate::parse::response(op, body)?;
+        crate::parse::check_strict(op, body)?;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let op = Operation::BarcodeLookup { ean: 5099750442227, language: None };
+        assert_eq!(op.query(), "&op=barcode-lookup&ean=5099750442227&language=1");
+        let op = Operation::IsbnLookup { isbn: 1119578884 };
+        assert_eq!(op.query(), "&op=barcode-lookup&isbn=1119578884");
+        let op = Operation::CategorySearch { category: 45, name: Some(String::from("bananaboat")), language: Some(1), page: PageRequest::default() };
+        assert_eq!(op.query(), "&op=category-search&category=45&name=bananaboat&language=1&page=0");
+        let op = Operation::CategorySearch { category: 45, name: None, language: None, page: PageRequest::new(2).unwrap() };
+        assert_eq!(op.query(), "&op=category-search&category=45&language=99&page=2");
+        assert_eq!(Operation::AccountStatus.query(), "&op=account-status");
+        let op = Operation::ProductSearch { name: String::from("AT&T C++ 50% #1 Café"), language: None, page: PageRequest::default() };
+        assert_eq!(op.query(), "&op=product-search&name=AT%26T%20C%2B%2B%2050%25%20%231%20Caf%C3%A9&language=99&page=0");
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_barcode_image_query() {
+        let op = Operation::BarcodeImage { ean: 12345670, symbology: Symbology::Ean8, width: None, height: None };
+        assert_eq!(op.query(), "&op=barcode-image&ean=12345670&width=102&height=50");
+        let op = Operation::BarcodeImage { ean: 12345670, symbology: Symbology::of(12345670), width: None, height: None };
+        assert_eq!(op.query(), "&op=barcode-image&ean=12345670&width=102&height=50");
+        assert_eq!(Symbology::of(5099750442227), Symbology::Ean13);
+        // an explicit symbology pads the code, eg. a UPC-A code to EAN-13
+        let op = Operation::BarcodeImage { ean: 36000291452, symbology: Symbology::Ean13, width: None, height: None };
+        assert_eq!(op.query(), "&op=barcode-image&ean=0036000291452&width=102&height=50");
+    }
+
+    #[test]
+    fn test_next_page() {
+        let op = Operation::ProductSearch { name: String::from("bananaboat"), language: None, page: PageRequest::default() };
+        let next = op.next_page().unwrap();
+        assert_eq!(next.page(), Some(PageRequest::new(1).unwrap()));
+        assert_eq!(Operation::IsbnLookup { isbn: 1119578884 }.page(), None);
+        assert_eq!(Operation::IsbnLookup { isbn: 1119578884 }.next_page(), None);
+    }
+
+    #[test]
+    fn test_credit_cost() {
+        assert_eq!(Operation::BarcodeLookup { ean: 5099750442227, language: None }.credit_cost(), 1);
+        assert_eq!(Operation::ProductSearch { name: String::from("bananaboat"), language: None, page: PageRequest::default() }.credit_cost(), 1);
+        assert_eq!(Operation::AccountStatus.credit_cost(), 0);
+    }
+
+    #[test]
+    fn test_default_language() {
+        let op = Operation::BarcodeLookup { ean: 5099750442227, language: None };
+        assert_eq!(op.with_default_language(3), Some(Operation::BarcodeLookup { ean: 5099750442227, language: Some(3) }));
+        let op = Operation::BarcodeLookup { ean: 5099750442227, language: Some(1) };
+        assert_eq!(op.with_default_language(3), None);
+        assert_eq!(Operation::AccountStatus.with_default_language(3), None);
+    }
+}
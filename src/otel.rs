@@ -0,0 +1,88 @@
+//! OpenTelemetry instrumentation of API calls (enabled with the `otel` feature)
+//!
+//! Every API call gets a client span named after the operation, using the global tracer provider
+//! and text map propagator configured by the application.
+
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry::propagation::Injector;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry_semantic_conventions::trace::{ERROR_TYPE, HTTP_REQUEST_METHOD, HTTP_RESPONSE_STATUS_CODE, SERVER_ADDRESS, URL_FULL};
+
+use crate::{ErrorKind, HttpRequest, HttpResponse, Transport};
+
+/// Adds the propagated trace context as HTTP headers to the outgoing request
+struct HeaderInjector<'a>(&'a mut Vec<(String, String)>);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.retain(|(name, _)| !name.eq_ignore_ascii_case(key));
+        self.0.push((key.to_string(), value));
+    }
+}
+
+/// Send the request inside a client span for this operation
+pub(crate) fn send<T: Transport>(transport: &T, operation: &str, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+    let tracer = global::tracer("eansearch");
+    let mut attributes = vec![
+        KeyValue::new(HTTP_REQUEST_METHOD, request.method),
+        KeyValue::new(URL_FULL, request.redacted_url()),
+        KeyValue::new("eansearch.operation", operation.to_string()),
+        KeyValue::new("eansearch.request_id", request.request_id.clone()),
+    ];
+    if let Some(host) = host(&request.url) {
+        attributes.push(KeyValue::new(SERVER_ADDRESS, host.to_string()));
+    }
+    let span = tracer
+        .span_builder(format!("{} {}", request.method, operation))
+        .with_kind(SpanKind::Client)
+        .with_attributes(attributes)
+        .start(&tracer);
+    let cx = Context::current_with_span(span);
+    let mut request = request.clone();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut HeaderInjector(&mut request.headers))
+    });
+
+    let response = transport.send(&request);
+    let span = cx.span();
+    match &response {
+        Ok(r) => {
+            span.set_attribute(KeyValue::new(HTTP_RESPONSE_STATUS_CODE, i64::from(r.status)));
+            if r.status >= 400 {
+                span.set_attribute(KeyValue::new(ERROR_TYPE, r.status.to_string()));
+                span.set_status(Status::error(format!("HTTP {}", r.status)));
+            }
+        }
+        Err(e) => {
+            let error_type = match e {
+                #[cfg(feature = "blocking")]
+                ErrorKind::Http(_) => "reqwest::Error",
+                _ => "transport",
+            };
+            span.set_attribute(KeyValue::new(ERROR_TYPE, error_type));
+            span.set_status(Status::error(crate::redact_token(&e.to_string())));
+        }
+    }
+    span.end();
+    response
+}
+
+/// The host name of a URL like `https://api.ean-search.org/api?...`
+fn host(url: &str) -> Option<&str> {
+    let authority = url.split_once("://")?.1.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host() {
+        assert_eq!(host("https://api.ean-search.org/api?format=json"), Some("api.ean-search.org"));
+        assert_eq!(host("http://user:pw@localhost:8080?op=x"), Some("localhost"));
+        assert_eq!(host("not a url"), None);
+    }
+}
@@ -0,0 +1,79 @@
+//! Pagination parameters for the list operations
+
+use std::error::Error;
+use std::fmt;
+
+/// The page of a result list to request from one of the search operations
+///
+/// The default is the first page (page 0).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PageRequest {
+    page: u32,
+}
+
+/// Error for a page number outside of 0..=[`PageRequest::MAX_PAGE`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidPage(pub i64);
+
+impl fmt::Display for InvalidPage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid page number {} (must be between 0 and {})", self.0, PageRequest::MAX_PAGE)
+    }
+}
+
+impl Error for InvalidPage {}
+
+impl PageRequest {
+    /// The highest page number the API accepts
+    pub const MAX_PAGE: u32 = i32::MAX as u32;
+
+    /// Request a specific page (counting from 0)
+    pub fn new(page: u32) -> Result<Self, InvalidPage> {
+        if page > Self::MAX_PAGE {
+            return Err(InvalidPage(i64::from(page)));
+        }
+        Ok(Self { page })
+    }
+
+    /// The page number (counting from 0)
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    /// The request for the following page (None after the last valid page)
+    pub fn next(&self) -> Option<Self> {
+        Self::new(self.page.checked_add(1)?).ok()
+    }
+}
+
+impl TryFrom<i32> for PageRequest {
+    type Error = InvalidPage;
+
+    fn try_from(page: i32) -> Result<Self, Self::Error> {
+        u32::try_from(page).map_err(|_| InvalidPage(i64::from(page))).and_then(Self::new)
+    }
+}
+
+impl TryFrom<u32> for PageRequest {
+    type Error = InvalidPage;
+
+    fn try_from(page: u32) -> Result<Self, Self::Error> {
+        Self::new(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_request() {
+        assert_eq!(PageRequest::default().page(), 0);
+        assert_eq!(PageRequest::new(3).unwrap().page(), 3);
+        assert_eq!(PageRequest::new(3).unwrap().next(), Some(PageRequest::new(4).unwrap()));
+        assert_eq!(PageRequest::try_from(2i32).unwrap().page(), 2);
+        assert_eq!(PageRequest::try_from(-1i32), Err(InvalidPage(-1)));
+        assert!(PageRequest::new(PageRequest::MAX_PAGE + 1).is_err());
+        assert_eq!(PageRequest::new(PageRequest::MAX_PAGE).unwrap().next(), None);
+    }
+}
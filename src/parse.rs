@@ -0,0 +1,422 @@
+//! Parsing of API responses
+//!
+//! All parsers return a typed error for malformed responses (truncated JSON, unexpected types,
+//! empty lists, HTML error pages, ...) and never panic.
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+#[cfg(feature = "image")]
+use base64::{Engine as _, engine::general_purpose};
+
+use crate::{ErrorKind, Operation, Product, Response};
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ProductCountry {
+    #[serde(with = "crate::serde_str")]
+    ean: u64,
+    #[serde(alias = "issuing_country")]
+    issuing_country: String,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct VerifyChecksum {
+    #[serde(with = "crate::serde_str")]
+    ean: u64,
+    valid: String,
+}
+
+#[cfg(feature = "image")]
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BarcodeImage {
+    #[serde(with = "crate::serde_str")]
+    ean: u64,
+    barcode: String,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AccountStatus {
+    id: String,
+    requests: u32,
+    #[serde(alias = "requestLimit", alias = "request_limit")]
+    requestlimit: u32,
+}
+
+/// Parse the response body of an operation
+pub(crate) fn response(op: &Operation, body: &str) -> Result<Response, ErrorKind> {
+    match op {
+        Operation::BarcodeLookup { .. } | Operation::IsbnLookup { .. } =>
+            product(op, body).map(Response::Product),
+        Operation::BarcodePrefixSearch { .. } | Operation::ProductSearch { .. } | Operation::CategorySearch { .. } =>
+            product_list(op, body).map(Response::ProductList),
+        Operation::IssuingCountry { .. } => issuing_country(op, body).map(Response::IssuingCountry),
+        Operation::VerifyChecksum { .. } => verify_checksum(op, body).map(Response::ChecksumValid),
+        #[cfg(feature = "image")]
+        Operation::BarcodeImage { .. } => barcode_image(op, body).map(Response::BarcodeImage),
+        Operation::AccountStatus => account_status(op, body).map(Response::AccountStatus),
+    }
+}
+
+/// A response the parser can't make sense of, with the start of the body for diagnosis
+pub(crate) fn unexpected(op: &Operation, body: &str) -> ErrorKind {
+    ErrorKind::UnexpectedResponse { operation: op.name().to_string(), excerpt: excerpt(body) }
+}
+
+/// The start of a response body, short enough for an error message
+pub(crate) fn excerpt(body: &str) -> String {
+    const MAX_EXCERPT: usize = 200;
+    let mut end = body.len().min(MAX_EXCERPT);
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    body[..end].to_string()
+}
+
+/// The JSON payload of a response, with error responses turned into errors
+///
+/// Single results come as an array (`[{"ean":...}]`), result lists and the account status
+/// as an object (`{"productlist":[...]}`) and errors as an array of `{"error":...}` objects.
+/// Anything that isn't JSON (eg. an HTML error page) is an unexpected response.
+fn envelope(op: &Operation, body: &str) -> Result<Value, ErrorKind> {
+    let json : Value = serde_json::from_str(body).map_err(|_e| unexpected(op, body))?;
+    let error = match &json {
+        Value::Array(list) => list.first().and_then(|entry| entry.get("error")),
+        Value::Object(map) => map.get("error"),
+        _ => None,
+    };
+    match error {
+        Some(Value::String(msg)) => Err(ErrorKind::api(msg.clone())),
+        Some(_) => Err(ErrorKind::UndefinedApiError),
+        None => Ok(json),
+    }
+}
+
+/// The first entry of a (single entry) result array
+fn first<T: DeserializeOwned>(op: &Operation, body: &str) -> Result<T, ErrorKind> {
+    match envelope(op, body)? {
+        Value::Array(list) => match list.into_iter().next() {
+            Some(entry) => Ok(serde_json::from_value(entry)?),
+            None => Err(ErrorKind::UndefinedApiError),
+        },
+        _ => Err(unexpected(op, body)),
+    }
+}
+
+/// The fields of an object response
+fn object(op: &Operation, body: &str) -> Result<Map<String, Value>, ErrorKind> {
+    match envelope(op, body)? {
+        Value::Object(map) => Ok(map),
+        _ => Err(unexpected(op, body)),
+    }
+}
+
+pub(crate) fn product(op: &Operation, body: &str) -> Result<Option<Product>, ErrorKind> {
+    match first::<Product>(op, body) {
+        Ok(p) => Ok(Some(p)), // EAN found
+        Err(e) if e.is_not_found() => Ok(None), // Rust has a better way to represent EAN not found
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) fn product_list(op: &Operation, body: &str) -> Result<Vec<Product>, ErrorKind> {
+    Ok(product_page(op, body)?.0)
+    // TODO: signal total list size?
+}
+
+/// A page of a product list and whether the API has more pages (None if the response doesn't say)
+pub(crate) fn product_page(op: &Operation, body: &str) -> Result<(Vec<Product>, Option<bool>), ErrorKind> {
+    // a missing list means a changed response format, not an empty result
+    let mut json = match object(op, body) {
+        Ok(json) => json,
+        // nothing found is an empty result, like an empty list
+        Err(e) if e.is_not_found() => return Ok((Vec::new(), Some(false))),
+        Err(e) => return Err(e),
+    };
+    let more = match json.get("moreproducts") {
+        Some(Value::Bool(more)) => Some(*more),
+        Some(Value::String(more)) if more == "true" || more == "1" => Some(true),
+        Some(Value::String(more)) if more == "false" || more == "0" => Some(false),
+        Some(Value::Number(more)) => more.as_u64().map(|more| more != 0),
+        _ => None,
+    };
+    let pl = ["productlist", "productList", "product_list"].iter().find_map(|key| json.remove(*key));
+    match pl {
+        Some(pl @ Value::Array(_)) => Ok((serde_json::from_value(pl)?, more)),
+        _ => Err(unexpected(op, body)),
+    }
+}
+
+pub(crate) fn issuing_country(op: &Operation, body: &str) -> Result<String, ErrorKind> {
+    Ok(first::<ProductCountry>(op, body)?.issuing_country)
+}
+
+pub(crate) fn verify_checksum(op: &Operation, body: &str) -> Result<bool, ErrorKind> {
+    Ok(first::<VerifyChecksum>(op, body)?.valid == "1")
+}
+
+pub(crate) fn account_status(op: &Operation, body: &str) -> Result<u32, ErrorKind> {
+    let status : AccountStatus = serde_json::from_value(Value::Object(object(op, body)?))?;
+    Ok(status.requestlimit.saturating_sub(status.requests))
+}
+
+#[cfg(feature = "image")]
+pub(crate) fn barcode_image(op: &Operation, body: &str) -> Result<Vec<u8>, ErrorKind> {
+    let image = first::<BarcodeImage>(op, body)?;
+    let png = general_purpose::STANDARD_NO_PAD.decode(image.barcode).map_err(ErrorKind::Base64)?;
+    check_png(&png)?;
+    Ok(png)
+}
+
+/// The keys of products, with their aliases
+const PRODUCT_KEYS: &[&str] = &["ean", "name", "categoryId", "category_id", "categoryName", "category_name",
+    "googleCategoryId", "google_category_id", "issuingCountry", "issuing_country"];
+/// The keys of a product list, besides the list itself
+const LIST_KEYS: &[&str] = &["page", "moreproducts", "totalproducts", "productlist", "productList", "product_list"];
+
+/// The keys of the single result (or the object) of an operation
+fn result_keys(op: &Operation) -> &'static [&'static str] {
+    match op {
+        Operation::BarcodeLookup { .. } | Operation::IsbnLookup { .. } => PRODUCT_KEYS,
+        Operation::BarcodePrefixSearch { .. } | Operation::ProductSearch { .. } | Operation::CategorySearch { .. } => LIST_KEYS,
+        Operation::IssuingCountry { .. } => &["ean", "issuingCountry", "issuing_country"],
+        Operation::VerifyChecksum { .. } => &["ean", "valid"],
+        #[cfg(feature = "image")]
+        Operation::BarcodeImage { .. } => &["ean", "barcode"],
+        Operation::AccountStatus => &["id", "requests", "requestlimit", "requestLimit", "request_limit"],
+    }
+}
+
+/// Check that a response has no fields the parsers don't know, for strict mode
+///
+/// Meant for responses that parsed successfully; error responses aren't checked.
+pub(crate) fn check_strict(op: &Operation, body: &str) -> Result<(), ErrorKind> {
+    let json : Value = serde_json::from_str(body).map_err(|_e| unexpected(op, body))?;
+    let mut unknown : Vec<String> = Vec::new();
+    let mut check = |value: &Value, known: &[&str]| {
+        if let Value::Object(map) = value {
+            if !map.contains_key("error") {
+                unknown.extend(map.keys().filter(|key| !known.contains(&key.as_str())).cloned());
+            }
+        }
+    };
+    match &json {
+        Value::Array(list) => list.iter().for_each(|entry| check(entry, result_keys(op))),
+        Value::Object(map) => {
+            check(&json, result_keys(op));
+            if let Some(Value::Array(list)) = ["productlist", "productList", "product_list"].iter().find_map(|key| map.get(*key)) {
+                list.iter().for_each(|product| check(product, PRODUCT_KEYS));
+            }
+        }
+        _ => return Err(unexpected(op, body)),
+    }
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        unknown.sort();
+        unknown.dedup();
+        Err(ErrorKind::UnknownFields { operation: op.name().to_string(), fields: unknown })
+    }
+}
+
+#[cfg(feature = "image")]
+/// Check the PNG signature and that the first chunk is a plausible image header
+fn check_png(png: &[u8]) -> Result<(), ErrorKind> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !png.starts_with(SIGNATURE) {
+        return Err(ErrorKind::InvalidImage("no PNG signature"));
+    }
+    // IHDR: length 13, type, width, height (4 bytes each), bit depth, color type, ...
+    match png.get(8..26) {
+        Some(ihdr) if ihdr[..8] == *b"\0\0\0\x0dIHDR" => {
+            let width = u32::from_be_bytes([ihdr[8], ihdr[9], ihdr[10], ihdr[11]]);
+            let height = u32::from_be_bytes([ihdr[12], ihdr[13], ihdr[14], ihdr[15]]);
+            if width == 0 || height == 0 {
+                return Err(ErrorKind::InvalidImage("empty PNG image"));
+            }
+            Ok(())
+        }
+        _ => Err(ErrorKind::InvalidImage("missing PNG header")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, PageRequest};
+    #[cfg(feature = "image")]
+    use crate::Symbology;
+
+    const LOOKUP: Operation = Operation::BarcodeLookup { ean: 5099750442227, language: None };
+    const ACCOUNT: Operation = Operation::AccountStatus;
+
+    fn search() -> Operation {
+        Operation::ProductSearch { name: String::from("bananaboat"), language: None, page: PageRequest::default() }
+    }
+
+    /// One operation of each response shape
+    fn all_ops() -> Vec<Operation> {
+        vec![
+            LOOKUP,
+            Operation::IsbnLookup { isbn: 1119578884 },
+            Operation::BarcodePrefixSearch { prefix: 509975044, language: None, page: PageRequest::default() },
+            search(),
+            Operation::CategorySearch { category: 45, name: None, language: None, page: PageRequest::default() },
+            Operation::IssuingCountry { ean: 5099750442227 },
+            Operation::VerifyChecksum { ean: 5099750442227 },
+            #[cfg(feature = "image")]
+            Operation::BarcodeImage { ean: 5099750442227, symbology: Symbology::Ean13, width: None, height: None },
+            ACCOUNT,
+        ]
+    }
+
+    #[test]
+    fn test_product() {
+        let p = product(&LOOKUP, fixtures::BARCODE_LOOKUP).unwrap().unwrap();
+        assert_eq!(p.ean, 5099750442227);
+        assert_eq!(p.category_id, 45);
+        assert_eq!(p.google_category_id, Some(855));
+        assert_eq!(p.to_string(), "EAN 5099750442227: Michael Jackson: Thriller (category 45: Music, google category 855) from UK");
+        assert!(product(&LOOKUP, fixtures::BARCODE_NOT_FOUND).unwrap().is_none());
+        assert!(matches!(product(&LOOKUP, fixtures::INVALID_TOKEN), Err(ErrorKind::InvalidToken(msg)) if msg == "Invalid token"));
+        assert!(p.extra.is_empty());
+        // new fields are kept, and written back
+        let body = r#"[{"ean":"5099750442227","name":"Thriller","category_id":"45","categoryName":"Music","issuingCountry":"UK","brand":"Epic"}]"#;
+        let p = product(&LOOKUP, body).unwrap().unwrap();
+        assert_eq!(p.category_id, 45);
+        assert_eq!(p.extra.keys().collect::<Vec<_>>(), ["brand"]);
+        assert_eq!(p.extra["brand"], "Epic");
+        assert_eq!(serde_json::to_value(&p).unwrap()["brand"], "Epic");
+    }
+
+    #[test]
+    fn test_product_page() {
+        assert_eq!(product_page(&search(), fixtures::PRODUCT_SEARCH).unwrap().1, Some(false));
+        let body = r#"{"page":"0","moreproducts":"true","productlist":[]}"#;
+        assert_eq!(product_page(&search(), body).unwrap().1, Some(true));
+        assert_eq!(product_page(&search(), r#"{"productlist":[]}"#).unwrap().1, None);
+        assert_eq!(product_page(&search(), fixtures::BARCODE_NOT_FOUND).unwrap().1, Some(false));
+    }
+
+    #[test]
+    fn test_product_list() {
+        let products = product_list(&search(), fixtures::PRODUCT_SEARCH).unwrap();
+        assert_eq!(products.len(), 2);
+        assert_eq!(products[0].ean, 16494600224);
+        assert_eq!(fixtures::product_list().len(), 2);
+        assert_eq!(fixtures::product().ean, 5099750442227);
+        assert!(product_list(&search(), fixtures::PRODUCT_SEARCH_EMPTY).unwrap().is_empty());
+        assert!(product_list(&search(), r#"{"error":"Barcode not found"}"#).unwrap().is_empty());
+        assert!(matches!(product_list(&search(), fixtures::INVALID_TOKEN), Err(ErrorKind::InvalidToken(_))));
+    }
+
+    #[test]
+    fn test_product_list_missing() {
+        for body in [r#"{"page":0}"#, r#"{"productlist":null}"#, "[]", fixtures::MAINTENANCE_PAGE] {
+            match product_list(&search(), body) {
+                Err(ErrorKind::UnexpectedResponse { operation, excerpt }) => {
+                    assert_eq!(operation, "product-search");
+                    assert_eq!(excerpt, &body[..body.len().min(200)]);
+                }
+                r => panic!("unexpected result for {:?}: {:?}", body, r),
+            }
+        }
+        let body = "ä".repeat(150);
+        match product_list(&search(), &body) {
+            Err(ErrorKind::UnexpectedResponse { excerpt, .. }) => assert_eq!(excerpt, "ä".repeat(100)),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_snake_case_keys() {
+        let body = r#"{"product_list":[{"ean":"5099750442227","name":"Thriller","category_id":"45","category_name":"Music","issuing_country":"UK"}]}"#;
+        let products = product_list(&search(), body).unwrap();
+        assert_eq!(products[0].category_id, 45);
+        assert_eq!(products[0].issuing_country, "UK");
+        assert_eq!(products[0].google_category_id, None);
+        let op = Operation::IssuingCountry { ean: 5099750442227 };
+        assert_eq!(issuing_country(&op, r#"[{"ean":"5099750442227","issuing_country":"UK"}]"#).unwrap(), "UK");
+        assert_eq!(account_status(&ACCOUNT, r#"{"id":"1","requests":10,"request_limit":100}"#).unwrap(), 90);
+    }
+
+    #[test]
+    fn test_single_results() {
+        let op = Operation::IssuingCountry { ean: 5099750442227 };
+        assert_eq!(issuing_country(&op, fixtures::ISSUING_COUNTRY).unwrap(), "UK");
+        let op = Operation::VerifyChecksum { ean: 5099750442227 };
+        assert!(verify_checksum(&op, fixtures::CHECKSUM_VALID).unwrap());
+        assert!(!verify_checksum(&op, fixtures::CHECKSUM_INVALID).unwrap());
+    }
+
+    #[test]
+    fn test_account_status() {
+        assert_eq!(account_status(&ACCOUNT, fixtures::ACCOUNT_STATUS).unwrap(), 90);
+        assert_eq!(account_status(&ACCOUNT, r#"{"id":"1","requests":110,"requestlimit":100}"#).unwrap(), 0);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_barcode_image() {
+        let op = Operation::BarcodeImage { ean: 5099750442227, symbology: Symbology::Ean13, width: None, height: None };
+        let png = barcode_image(&op, fixtures::BARCODE_IMAGE).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_eq!(png.len(), 67);
+        assert!(matches!(barcode_image(&op, r#"[{"ean":"5099750442227","barcode":"%%%"}]"#), Err(ErrorKind::Base64(_))));
+        // base64 of "Invalid barcode", of the PNG signature only and of a 0x0 pixel PNG
+        for barcode in ["SW52YWxpZCBiYXJjb2Rl", "iVBORw0KGgo", "iVBORw0KGgoAAAANSUhEUgAAAAAAAAAAAQAAAAA"] {
+            let body = format!(r#"[{{"ean":"5099750442227","barcode":"{}"}}]"#, barcode);
+            assert!(matches!(barcode_image(&op, &body), Err(ErrorKind::InvalidImage(_))), "{}", barcode);
+        }
+    }
+
+    #[test]
+    fn test_check_strict() {
+        for op in all_ops() {
+            assert!(check_strict(&op, fixtures::body(&op)).is_ok(), "{}", op.name());
+        }
+        assert!(check_strict(&LOOKUP, fixtures::BARCODE_NOT_FOUND).is_ok());
+        let body = r#"[{"ean":"5099750442227","name":"Thriller","categoryId":"45","categoryName":"Music","issuingCountry":"UK","brand":"Epic"}]"#;
+        assert!(matches!(check_strict(&LOOKUP, body), Err(ErrorKind::UnknownFields { fields, .. }) if fields == ["brand"]));
+        let body = r#"{"page":"0","cursor":"x","productlist":[{"ean":"5099750442227","name":"Thriller","categoryId":"45","categoryName":"Music","issuingCountry":"UK","brand":"Epic"}]}"#;
+        assert!(matches!(check_strict(&search(), body), Err(ErrorKind::UnknownFields { fields, .. }) if fields == ["brand", "cursor"]));
+    }
+
+    #[test]
+    fn test_envelopes() {
+        for op in all_ops() {
+            // errors are recognized in both envelopes
+            for body in [fixtures::INVALID_TOKEN, r#"{"error":"Invalid token"}"#] {
+                assert!(matches!(response(&op, body), Err(ErrorKind::InvalidToken(msg)) if msg == "Invalid token"), "{}: {}", op.name(), body);
+            }
+            assert!(response(&op, fixtures::body(&op)).is_ok(), "{}", op.name());
+            assert!(matches!(response(&op, fixtures::RATE_LIMITED), Err(ErrorKind::RateLimited { retry_after: None })), "{}", op.name());
+            assert!(matches!(response(&op, fixtures::MAINTENANCE_PAGE), Err(ErrorKind::UnexpectedResponse { .. })), "{}", op.name());
+            assert!(matches!(response(&op, "null"), Err(ErrorKind::UnexpectedResponse { .. })), "{}", op.name());
+        }
+        // an array where an object is expected and vice versa
+        assert!(matches!(response(&LOOKUP, fixtures::PRODUCT_SEARCH), Err(ErrorKind::UnexpectedResponse { .. })));
+        assert!(matches!(response(&search(), fixtures::BARCODE_LOOKUP), Err(ErrorKind::UnexpectedResponse { .. })));
+        assert!(matches!(response(&ACCOUNT, fixtures::ISSUING_COUNTRY), Err(ErrorKind::UnexpectedResponse { .. })));
+    }
+
+    #[test]
+    fn test_malformed_bodies() {
+        let malformed = [
+            "", "null", "[]", "{}", "[null]", "[{}]", "42", "\"text\"",
+            &fixtures::BARCODE_LOOKUP[..fixtures::BARCODE_LOOKUP.len() / 2], // truncated
+            r#"[{"ean":5099750442227,"name":1}]"#, // wrong types
+            r#"[{"ean":"abc","name":"x","categoryId":"45","categoryName":"Music","issuingCountry":"UK"}]"#,
+            r#"[{"error":42}]"#,
+            fixtures::MAINTENANCE_PAGE,
+        ];
+        for body in malformed {
+            for op in &all_ops() {
+                assert!(response(op, body).is_err(), "{} accepted {:?}", op.name(), body);
+            }
+        }
+    }
+}
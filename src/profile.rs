@@ -0,0 +1,160 @@
+//! Named configuration profiles, eg. for dev, staging and production
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{EANSearchError, ErrorKind};
+
+/// The config file used by [`Profile::load`] unless `EANSEARCH_CONFIG` names another one
+pub const CONFIG_FILE: &str = "eansearch.json";
+
+/// The settings for an [`EANSearch`](crate::EANSearch) client, see [`EANSearch::profile`](crate::EANSearch::profile)
+#[derive(Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// The API token
+    pub token: String,
+    /// The API endpoint (None for the default endpoint)
+    pub base_url: Option<String>,
+    /// The language used when a method is called without one
+    pub language: Option<i8>,
+    /// Warn below this many credits, see [`QuotaGuard`](crate::QuotaGuard)
+    pub soft_limit: Option<u32>,
+    /// Stop making calls below this many credits, see [`QuotaGuard`](crate::QuotaGuard)
+    pub hard_limit: Option<u32>,
+}
+
+impl std::fmt::Debug for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // don't leak the token into logs
+        f.debug_struct("Profile")
+            .field("base_url", &self.base_url)
+            .field("language", &self.language)
+            .field("soft_limit", &self.soft_limit)
+            .field("hard_limit", &self.hard_limit)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A profile as read from the config file, all settings optional
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Settings {
+    token: Option<String>,
+    base_url: Option<String>,
+    language: Option<i8>,
+    soft_limit: Option<u32>,
+    hard_limit: Option<u32>,
+}
+
+impl Profile {
+    /// Load a profile from the config file and the environment
+    ///
+    /// The config file (`EANSEARCH_CONFIG`, or `eansearch.json` in the current directory) is a JSON object
+    /// with a profile for each name, eg. `{"staging": {"token": "...", "language": 1, "soft_limit": 1000}}`.
+    /// It's optional, and its settings are overridden by the environment variables
+    /// `EANSEARCH_<NAME>_TOKEN`, `_BASE_URL`, `_LANGUAGE`, `_SOFT_LIMIT` and `_HARD_LIMIT`
+    /// (with the name in upper case and '-' replaced by '_', eg. `EANSEARCH_STAGING_TOKEN`).
+    pub fn load(name: &str) -> Result<Profile, EANSearchError> {
+        let path = std::env::var("EANSEARCH_CONFIG");
+        let settings = match &path {
+            Ok(path) => read_settings(Path::new(path), name)?,
+            Err(_) if Path::new(CONFIG_FILE).exists() => read_settings(Path::new(CONFIG_FILE), name)?,
+            Err(_) => None,
+        };
+        let settings = from_env(settings.unwrap_or_default(), name, |var| std::env::var(var).ok())?;
+        to_profile(settings, name)
+    }
+
+    /// Read a profile from a config file (see [`load`](Self::load) for the format), without the environment
+    pub fn from_file(path: impl AsRef<Path>, name: &str) -> Result<Profile, EANSearchError> {
+        let settings = read_settings(path.as_ref(), name)?
+            .ok_or_else(|| ErrorKind::Config(format!("no profile '{}' in {}", name, path.as_ref().display())))?;
+        to_profile(settings, name)
+    }
+}
+
+fn read_settings(path: &Path, name: &str) -> Result<Option<Settings>, ErrorKind> {
+    let json = std::fs::read_to_string(path).map_err(|e| ErrorKind::Config(format!("{}: {}", path.display(), e)))?;
+    parse_settings(&json, name).map_err(|e| ErrorKind::Config(format!("{}: {}", path.display(), e)))
+}
+
+fn parse_settings(json: &str, name: &str) -> Result<Option<Settings>, serde_json::Error> {
+    let mut profiles : HashMap<String, Settings> = serde_json::from_str(json)?;
+    Ok(profiles.remove(name))
+}
+
+fn from_env(mut settings: Settings, name: &str, var: impl Fn(&str) -> Option<String>) -> Result<Settings, ErrorKind> {
+    let prefix = format!("EANSEARCH_{}_", name.to_uppercase().replace('-', "_"));
+    let var = |suffix: &str| var(&(prefix.clone() + suffix)).map(|value| (prefix.clone() + suffix, value));
+    fn number<T: std::str::FromStr>((var, value): (String, String)) -> Result<T, ErrorKind> {
+        value.trim().parse().map_err(|_| ErrorKind::Config(format!("{} is not a valid number: {}", var, value)))
+    }
+    if let Some((_, token)) = var("TOKEN") {
+        settings.token = Some(token);
+    }
+    if let Some((_, base_url)) = var("BASE_URL") {
+        settings.base_url = Some(base_url);
+    }
+    if let Some(value) = var("LANGUAGE") {
+        settings.language = Some(number(value)?);
+    }
+    if let Some(value) = var("SOFT_LIMIT") {
+        settings.soft_limit = Some(number(value)?);
+    }
+    if let Some(value) = var("HARD_LIMIT") {
+        settings.hard_limit = Some(number(value)?);
+    }
+    Ok(settings)
+}
+
+fn to_profile(settings: Settings, name: &str) -> Result<Profile, EANSearchError> {
+    let token = settings.token.ok_or_else(|| ErrorKind::Config(format!("no token for profile '{}'", name)))?;
+    Ok(Profile {
+        token,
+        base_url: settings.base_url,
+        language: settings.language,
+        soft_limit: settings.soft_limit,
+        hard_limit: settings.hard_limit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG : &str = r#"{
+        "dev": {"token": "dev-token", "base_url": "http://localhost:8080/api"},
+        "prod": {"token": "prod-token", "language": 3, "soft_limit": 1000, "hard_limit": 100}
+    }"#;
+
+    #[test]
+    fn test_parse_settings() {
+        let prod = to_profile(parse_settings(CONFIG, "prod").unwrap().unwrap(), "prod").unwrap();
+        assert_eq!(prod.token, "prod-token");
+        assert!(!format!("{:?}", prod).contains("prod-token"));
+        assert_eq!(prod.base_url, None);
+        assert_eq!((prod.language, prod.soft_limit, prod.hard_limit), (Some(3), Some(1000), Some(100)));
+        assert!(parse_settings(CONFIG, "staging").unwrap().is_none());
+        assert!(parse_settings(r#"{"dev": {"tokn": "typo"}}"#, "dev").is_err());
+        let e = to_profile(Settings::default(), "staging").unwrap_err();
+        assert_eq!(e.to_string(), "configuration: no token for profile 'staging'");
+    }
+
+    #[test]
+    fn test_from_env() {
+        let env = |var: &str| match var {
+            "EANSEARCH_STAGING_EU_TOKEN" => Some(String::from("env-token")),
+            "EANSEARCH_STAGING_EU_LANGUAGE" => Some(String::from("6")),
+            _ => None,
+        };
+        let settings = Settings { token: Some(String::from("file-token")), soft_limit: Some(500), ..Settings::default() };
+        let settings = from_env(settings, "staging-eu", env).unwrap();
+        assert_eq!(settings.token.as_deref(), Some("env-token"));
+        assert_eq!(settings.language, Some(6));
+        assert_eq!(settings.soft_limit, Some(500));
+        let env = |var: &str| (var == "EANSEARCH_DEV_HARD_LIMIT").then(|| String::from("lots"));
+        assert!(from_env(Settings::default(), "dev", env).is_err());
+    }
+}
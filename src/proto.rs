@@ -0,0 +1,233 @@
+//! The IO-free core of the client: building requests and parsing responses
+//!
+//! [`EANSearch`](crate::EANSearch) sends the requests with reqwest, but any HTTP client
+//! (async, embedded, a test double) can use the same request construction and parsing:
+//! build the request with [`Endpoint::request`], send it, and pass the body to [`parse_body`].
+
+use std::time::Duration;
+
+use crate::{EANSearchError, ErrorKind, Operation, Response};
+
+/// The API endpoint
+pub const API_URL: &str = "https://api.ean-search.org/api";
+
+/// The environment variable with another base URL for the clients, eg. a mock server in CI
+pub const BASE_URL_VAR: &str = "EANSEARCH_BASE_URL";
+
+/// The header the request ID is sent in, to correlate a request with the server logs
+pub const REQUEST_ID_HEADER: &str = "X-Request-ID";
+
+/// The base URL of clients constructed without one: [`BASE_URL_VAR`] if set, otherwise [`API_URL`]
+pub(crate) fn default_url() -> String {
+    std::env::var(BASE_URL_VAR).ok().filter(|url| !url.is_empty()).unwrap_or_else(|| API_URL.to_string())
+}
+
+/// An API endpoint together with the API token
+#[derive(Clone)]
+pub struct Endpoint {
+    base_url: String,
+}
+
+impl Endpoint {
+    /// The default endpoint with your API token
+    pub fn new(token: &str) -> Self {
+        Self::with_url(API_URL, token)
+    }
+
+    /// Another endpoint, eg. a mock server in tests
+    pub fn with_url(url: &str, token: &str) -> Self {
+        Self { base_url: format!("{}?format=json&token={}", url, encode(token)) }
+    }
+
+    /// The HTTP request for an operation
+    pub fn request(&self, op: &Operation) -> HttpRequest {
+        self.request_with_params(op, &[])
+    }
+
+    /// The HTTP request for an operation with additional query parameters,
+    /// eg. new API filters the operation doesn't have a field for yet
+    pub fn request_with_params(&self, op: &Operation, extra: &[(&str, &str)]) -> HttpRequest {
+        let mut url = self.base_url.clone() + &op.query();
+        for (name, value) in extra {
+            url = url + "&" + &encode(name) + "=" + &encode(value);
+        }
+        HttpRequest { method: "GET", url, request_id: new_request_id(), headers: Vec::new(), timeout: None }
+    }
+}
+
+impl std::fmt::Debug for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Endpoint").field("base_url", &crate::redact_token(&self.base_url)).finish()
+    }
+}
+
+/// An HTTP request to send to the API
+///
+/// The URL contains the API token, see [`redacted_url`](Self::redacted_url) for logging.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: &'static str,
+    pub url: String,
+    /// A random ID to send in the [`REQUEST_ID_HEADER`], eg. to reference the request in a support ticket
+    pub request_id: String,
+    /// More headers to send, eg. the trace context
+    pub headers: Vec<(String, String)>,
+    /// The longest the whole request (connecting, sending it and reading the response) may take
+    pub timeout: Option<Duration>,
+}
+
+impl HttpRequest {
+    /// The URL with the API token replaced by `***`
+    pub fn redacted_url(&self) -> String {
+        crate::redact_token(&self.url)
+    }
+}
+
+impl std::fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &self.redacted_url())
+            .field("request_id", &self.request_id)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// An HTTP response received by a transport, eg. a [`Transport`](crate::Transport)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// The value of a header (the name is case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse a response body received for an operation (bodies that aren't UTF-8 are unexpected responses)
+pub fn parse_body(op: &Operation, body: &[u8]) -> Result<Response, EANSearchError> {
+    Response::parse(op, body_str(op, body)?)
+}
+
+/// The remaining credits from the value of the `x-credits-remaining` header
+pub fn parse_credits_header(header: &str) -> Option<u32> {
+    header.trim().parse().ok()
+}
+
+/// The delay from the value of a `Retry-After` header (in seconds; HTTP dates aren't supported)
+pub fn parse_retry_after(header: &str) -> Option<Duration> {
+    header.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// The error for a response with an HTTP status the API uses instead of an error message
+///
+/// Other statuses are left to the parsers, the API sends its error messages with them.
+fn check_status(response: &HttpResponse) -> Result<(), ErrorKind> {
+    match response.status {
+        429 => Err(ErrorKind::RateLimited { retry_after: response.header("retry-after").and_then(parse_retry_after) }),
+        _ => Ok(()),
+    }
+}
+
+/// The result of an operation from its response, with the unknown fields checked in strict mode
+pub(crate) fn read_response<R>(
+    op: &Operation,
+    response: &HttpResponse,
+    strict: bool,
+    parse: impl FnOnce(&Operation, &str) -> Result<R, ErrorKind>,
+) -> Result<R, ErrorKind> {
+    check_status(response)?;
+    let body = body_str(op, &response.body)?;
+    let result = parse(op, body).map_err(|e| match e {
+        // whatever the API says, it's because there are no credits left
+        ErrorKind::Api(msg) if response.header("x-credits-remaining").and_then(parse_credits_header) == Some(0) =>
+            ErrorKind::QuotaExhausted(msg),
+        e => e,
+    })?;
+    if strict {
+        crate::parse::check_strict(op, body)?;
+    }
+    Ok(result)
+}
+
+/// Percent-encode everything but the unreserved characters of RFC 3986
+pub(crate) fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            encoded.push(char::from(b));
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+/// A new random request ID of 16 hex digits
+pub(crate) fn new_request_id() -> String {
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    // the counter keeps IDs unique even if the random keys repeat
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    format!("{:016x}", hasher.finish())
+}
+
+/// The body as text, for the parsers
+pub(crate) fn body_str<'a>(op: &Operation, body: &'a [u8]) -> Result<&'a str, ErrorKind> {
+    std::str::from_utf8(body).map_err(|_e| crate::parse::unexpected(op, &String::from_utf8_lossy(body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_request() {
+        let endpoint = Endpoint::new("secret");
+        let request = endpoint.request(&Operation::AccountStatus);
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "https://api.ean-search.org/api?format=json&token=secret&op=account-status");
+        assert!(!format!("{:?} {:?}", request, endpoint).contains("secret"));
+        assert_eq!(request.request_id.len(), 16);
+        assert_ne!(request.request_id, endpoint.request(&Operation::AccountStatus).request_id);
+        let request = Endpoint::with_url("http://localhost:8080/api", "t").request(&Operation::AccountStatus);
+        assert_eq!(request.redacted_url(), "http://localhost:8080/api?format=json&token=***&op=account-status");
+        let request = Endpoint::new("a&b+c").request(&Operation::AccountStatus);
+        assert!(request.url.contains("token=a%26b%2Bc&op="));
+        assert_eq!(request.redacted_url(), "https://api.ean-search.org/api?format=json&token=***&op=account-status");
+    }
+
+    #[test]
+    fn test_request_with_params() {
+        let endpoint = Endpoint::new("secret");
+        let request = endpoint.request_with_params(&Operation::AccountStatus, &[("min-date", "2024-01-01"), ("q", "a&b=ü c")]);
+        assert!(request.url.ends_with("&op=account-status&min-date=2024-01-01&q=a%26b%3D%C3%BC%20c"));
+    }
+
+    #[test]
+    fn test_header() {
+        let response = HttpResponse { status: 200, headers: vec![(String::from("X-Credits-Remaining"), String::from("99"))], body: Vec::new() };
+        assert_eq!(response.header("x-credits-remaining"), Some("99"));
+        assert_eq!(response.header("content-type"), None);
+    }
+
+    #[test]
+    fn test_parse_body() {
+        let op = Operation::BarcodeLookup { ean: 5099750442227, language: None };
+        assert!(matches!(parse_body(&op, fixtures::BARCODE_LOOKUP.as_bytes()), Ok(Response::Product(Some(_)))));
+        let e = parse_body(&op, b"\xff\xfe<html>").unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::UnexpectedResponse { .. }));
+        assert_eq!(parse_credits_header(" 1234 "), Some(1234));
+        assert_eq!(parse_credits_header("many"), None);
+        assert_eq!(parse_retry_after("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+}
@@ -0,0 +1,56 @@
+//! HTTP proxy configuration
+
+use crate::ErrorKind;
+
+/// Credentials for an HTTP proxy, see [`EANSearchBuilder::proxy`](crate::EANSearchBuilder::proxy)
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum ProxyAuth {
+    /// Basic authentication with a user name and password
+    Basic { user: String, password: String },
+    /// A bearer token
+    Bearer(String),
+}
+
+impl std::fmt::Debug for ProxyAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // don't leak the credentials into logs
+        match self {
+            ProxyAuth::Basic { user, .. } => f.debug_struct("Basic").field("user", user).finish_non_exhaustive(),
+            ProxyAuth::Bearer(_) => f.write_str("Bearer"),
+        }
+    }
+}
+
+/// A proxy for all requests, bypassed for the hosts in `NO_PROXY`
+pub(crate) fn proxy(url: &str, auth: Option<&ProxyAuth>) -> Result<reqwest::Proxy, ErrorKind> {
+    let proxy = reqwest::Proxy::all(url)?.no_proxy(reqwest::NoProxy::from_env());
+    Ok(match auth {
+        None => proxy,
+        Some(ProxyAuth::Basic { user, password }) => proxy.basic_auth(user, password),
+        Some(ProxyAuth::Bearer(token)) => {
+            let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| ErrorKind::InvalidArgument(String::from("invalid proxy bearer token")))?;
+            value.set_sensitive(true);
+            proxy.custom_http_auth(value)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy() {
+        let auth = ProxyAuth::Basic { user: String::from("alice"), password: String::from("secret") };
+        assert!(proxy("http://proxy.example.com:3128", Some(&auth)).is_ok());
+        assert!(!format!("{:?}", auth).contains("secret"));
+        assert!(proxy("http://proxy.example.com:3128", Some(&ProxyAuth::Bearer(String::from("abc")))).is_ok());
+        assert!(matches!(
+            proxy("http://proxy.example.com:3128", Some(&ProxyAuth::Bearer(String::from("a\nb")))),
+            Err(ErrorKind::InvalidArgument(_))
+        ));
+        assert!(proxy("not a url", None).is_err());
+    }
+}
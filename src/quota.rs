@@ -0,0 +1,114 @@
+//! Guards against using up the credits of an account
+
+use std::sync::Mutex;
+
+type SoftLimitHook = Box<dyn Fn(u32) + Send + Sync>;
+
+/// Soft and hard thresholds for the remaining credits
+///
+/// When the `x-credits-remaining` header of a response drops below the soft threshold,
+/// the hook registered with [`on_soft_limit`](Self::on_soft_limit) is called (once, until the credits
+/// are above the threshold again) and a warning is logged. Once it drops below the hard threshold,
+/// API calls fail with [`ErrorKind::QuotaGuardTripped`](crate::ErrorKind::QuotaGuardTripped) without
+/// sending a request, except for the free account status, which re-arms the guard once the credits are renewed.
+/// Register it with [`EANSearch::with_quota_guard`](crate::EANSearch::with_quota_guard).
+pub struct QuotaGuard {
+    soft: u32,
+    hard: u32,
+    hook: Option<SoftLimitHook>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    remaining: Option<u32>,
+    warned: bool,
+}
+
+impl QuotaGuard {
+    /// Warn below `soft` credits, stop making calls below `hard` credits
+    pub fn new(soft: u32, hard: u32) -> Self {
+        Self { soft, hard, hook: None, state: Mutex::new(State::default()) }
+    }
+
+    /// Call a function with the remaining credits when they drop below the soft threshold
+    pub fn on_soft_limit<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(u32) + Send + Sync + 'static,
+    {
+        self.hook = Some(Box::new(hook));
+        self
+    }
+
+    /// The soft threshold
+    pub fn soft(&self) -> u32 {
+        self.soft
+    }
+
+    /// The hard threshold
+    pub fn hard(&self) -> u32 {
+        self.hard
+    }
+
+    /// The credits remaining according to the last response, if it had the header
+    pub fn credits_remaining(&self) -> Option<u32> {
+        self.state().remaining
+    }
+
+    /// Whether further calls are blocked because the credits dropped below the hard threshold
+    pub fn is_tripped(&self) -> bool {
+        self.credits_remaining().is_some_and(|credits| credits < self.hard)
+    }
+
+    /// Record the `x-credits-remaining` value of a response
+    pub fn observe(&self, credits_remaining: u32) {
+        let warn = {
+            let mut state = self.state();
+            state.remaining = Some(credits_remaining);
+            let below = credits_remaining < self.soft;
+            let warn = below && !state.warned;
+            state.warned = below;
+            warn
+        };
+        if warn {
+            #[cfg(feature = "log")]
+            log::warn!("only {} credits remaining (soft limit {})", credits_remaining, self.soft);
+            if let Some(hook) = &self.hook {
+                hook(credits_remaining);
+            }
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_quota_guard() {
+        let warnings = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&warnings);
+        let guard = QuotaGuard::new(1000, 100).on_soft_limit(move |_| { counter.fetch_add(1, Ordering::SeqCst); });
+        assert!(!guard.is_tripped());
+        guard.observe(1500);
+        assert_eq!(warnings.load(Ordering::SeqCst), 0);
+        guard.observe(999);
+        guard.observe(500);
+        assert_eq!(warnings.load(Ordering::SeqCst), 1); // only once
+        assert!(!guard.is_tripped());
+        guard.observe(99);
+        assert!(guard.is_tripped());
+        assert_eq!(guard.credits_remaining(), Some(99));
+        // renewed credits re-arm the warning
+        guard.observe(5000);
+        assert!(!guard.is_tripped());
+        guard.observe(900);
+        assert_eq!(warnings.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,55 @@
+//! Random valid EAN codes, eg. for load tests and demos
+
+use rand::Rng;
+
+use crate::gs1::member_prefixes;
+use crate::Ean;
+
+/// A random EAN-13 with valid check digit and a GS1 prefix of a member organization,
+/// optionally of a specific country (as code returned by the API, eg. "DE")
+///
+/// Returns None if there is no GS1 prefix for the country.
+pub fn random_ean13<R: Rng + ?Sized>(rng: &mut R, country: Option<&str>) -> Option<Ean> {
+    let prefix = random_prefix(rng, country, 0)?;
+    Some(Ean::complete(u64::from(prefix) * 1_000_000_000 + rng.random_range(0..1_000_000_000), 13))
+}
+
+/// A random EAN-8 with valid check digit and a GS1 prefix of a member organization
+pub fn random_ean8<R: Rng + ?Sized>(rng: &mut R) -> Ean {
+    // EAN-8 codes starting with 0 or 2 are for restricted circulation
+    let prefix = random_prefix(rng, None, 300).unwrap_or(400);
+    Ean::complete(u64::from(prefix) * 10_000 + rng.random_range(0..10_000), 8)
+}
+
+/// A random prefix from the member organization ranges of a country, not below min
+fn random_prefix<R: Rng + ?Sized>(rng: &mut R, country: Option<&str>, min: u16) -> Option<u16> {
+    let prefixes : Vec<u16> = member_prefixes(country).filter(|p| *p >= min).collect();
+    if prefixes.is_empty() {
+        return None;
+    }
+    Some(prefixes[rng.random_range(0..prefixes.len())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Allocation;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let ean = random_ean13(&mut rng, None).unwrap();
+            assert_eq!(ean.digits(), 13);
+            assert!(matches!(ean.allocation(), Ok(Allocation::MemberOrganization(_))));
+            let ean = random_ean13(&mut rng, Some("DE")).unwrap();
+            assert_eq!(ean.allocation(), Ok(Allocation::MemberOrganization("DE")));
+            assert!(Ean::parse(&ean.to_string()).is_ok());
+            let ean = random_ean8(&mut rng);
+            assert_eq!(ean.digits(), 8);
+            assert!(!ean.is_restricted_circulation());
+        }
+        assert_eq!(random_ean13(&mut rng, Some("XX")), None);
+    }
+}
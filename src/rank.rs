@@ -0,0 +1,100 @@
+//! Client-side re-ranking of search results
+
+use crate::Product;
+
+/// Lowercase words of a text, split at everything that isn't alphanumeric
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Reorder search results by relevance of their names to the query, best match first
+///
+/// The score is a simplified BM25 over the result set: query words that occur in few of the
+/// results count more, and matches in short names count more than in long ones.
+/// Results with the same score keep the order the API returned them in.
+pub fn rank_results(query: &str, products: Vec<Product>) -> Vec<Product> {
+    const K1: f64 = 1.2;
+    const B: f64 = 0.75;
+
+    let mut query = words(query);
+    query.sort();
+    query.dedup();
+    let names : Vec<Vec<String>> = products.iter().map(|p| words(&p.name)).collect();
+    let count = names.len() as f64;
+    let avg_len = names.iter().map(Vec::len).sum::<usize>() as f64 / count.max(1.0);
+    let idf : Vec<f64> = query.iter().map(|q| {
+        let containing = names.iter().filter(|name| name.contains(q)).count() as f64;
+        ((count - containing + 0.5) / (containing + 0.5) + 1.0).ln()
+    }).collect();
+
+    let mut scored : Vec<(f64, Product)> = names.iter().zip(products).map(|(name, product)| {
+        let norm = K1 * (1.0 - B + B * name.len() as f64 / avg_len.max(1.0));
+        let score = query.iter().zip(&idf).map(|(q, idf)| {
+            let tf = name.iter().filter(|w| *w == q).count() as f64;
+            idf * tf * (K1 + 1.0) / (tf + norm)
+        }).sum();
+        (score, product)
+    }).collect();
+    // sort_by is stable, so ties keep the API order
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().map(|(_, product)| product).collect()
+}
+
+/// Sort results by the edit distance between their name and the query, closest first,
+/// and drop all results with a distance above `max_distance`
+///
+/// The comparison is case insensitive. Results with the same distance keep the order the API
+/// returned them in, so the outcome only depends on the input.
+#[cfg(feature = "fuzzy")]
+pub fn rank_by_edit_distance(query: &str, products: Vec<Product>, max_distance: usize) -> Vec<Product> {
+    let query = query.to_lowercase();
+    let mut scored : Vec<(usize, Product)> = products.into_iter()
+        .map(|product| (strsim::levenshtein(&query, &product.name.to_lowercase()), product))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    scored.sort_by_key(|(distance, _)| *distance);
+    scored.into_iter().map(|(_, product)| product).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(ean: u64, name: &str) -> Product {
+        Product { ean, name: name.to_string(), category_id: 45, category_name: String::from("Music"), google_category_id: None, issuing_country: String::from("UK"), extra: Default::default() }
+    }
+
+    #[test]
+    fn test_rank_results() {
+        let products = vec![
+            product(1, "Greatest Hits Vol. 2"),
+            product(2, "Harry Belafonte: Banana Boat Song and other Calypso Classics"),
+            product(3, "Banana Boat"),
+            product(4, "Boat Shoes"),
+        ];
+        let ranked : Vec<u64> = rank_results("banana boat", products.clone()).iter().map(|p| p.ean).collect();
+        assert_eq!(ranked, vec![3, 2, 4, 1]);
+        // no matching words at all: API order
+        let ranked : Vec<u64> = rank_results("xyz", products).iter().map(|p| p.ean).collect();
+        assert_eq!(ranked, vec![1, 2, 3, 4]);
+        assert!(rank_results("banana", Vec::new()).is_empty());
+    }
+
+    #[cfg(feature = "fuzzy")]
+    #[test]
+    fn test_rank_by_edit_distance() {
+        let products = vec![
+            product(1, "Banana Boats"),
+            product(2, "Greatest Hits"),
+            product(3, "banana boat"),
+            product(4, "Banana Coat"),
+        ];
+        let ranked : Vec<u64> = rank_by_edit_distance("Banana Boat", products.clone(), 1).iter().map(|p| p.ean).collect();
+        assert_eq!(ranked, vec![3, 1, 4]);
+        let ranked : Vec<u64> = rank_by_edit_distance("Banana Boat", products, 0).iter().map(|p| p.ean).collect();
+        assert_eq!(ranked, vec![3]);
+    }
+}
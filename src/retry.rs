@@ -0,0 +1,91 @@
+//! Retrying transient failures within a budget for a whole batch
+
+use std::time::{Duration, Instant};
+
+/// Limits for retrying failed lookups in a batch, see [`EANSearch::lookup_many_with_retries`](crate::EANSearch::lookup_many_with_retries)
+///
+/// Once the budget is used up, remaining failures are reported instead of retried,
+/// so a flaky period can't multiply the runtime of a batch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryBudget {
+    /// Total number of retries for the whole batch
+    pub max_retries: u32,
+    /// Total time the batch may spend on retries, including the waits (None for no limit)
+    pub max_time: Option<Duration>,
+    /// Number of retries for a single item
+    pub per_item: u32,
+    /// Wait before a retry, doubled for each further retry of the same item
+    pub backoff: Duration,
+}
+
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self { max_retries: 10, max_time: Some(Duration::from_secs(60)), per_item: 3, backoff: Duration::from_secs(1) }
+    }
+}
+
+impl RetryBudget {
+    /// No retries at all
+    pub const NONE: RetryBudget = RetryBudget { max_retries: 0, max_time: None, per_item: 0, backoff: Duration::ZERO };
+}
+
+/// What is left of a budget during a batch
+pub(crate) struct RetryState {
+    budget: RetryBudget,
+    retries: u32,
+    spent: Duration,
+}
+
+impl RetryState {
+    pub(crate) fn new(budget: RetryBudget) -> Self {
+        Self { budget, retries: 0, spent: Duration::ZERO }
+    }
+
+    /// The wait before retry number `attempt` (from 1) of an item, at least the `retry_after` delay the server asked for,
+    /// None if the budget doesn't allow it
+    pub(crate) fn take(&mut self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if attempt > self.budget.per_item || self.retries >= self.budget.max_retries {
+            return None;
+        }
+        let backoff = self.budget.backoff.saturating_mul(2u32.saturating_pow(attempt - 1));
+        let wait = backoff.max(retry_after.unwrap_or_default());
+        if self.budget.max_time.is_some_and(|max| self.spent.saturating_add(wait) > max) {
+            return None;
+        }
+        self.retries += 1;
+        Some(wait)
+    }
+
+    /// Account for time spent on a retry (the wait and the request)
+    pub(crate) fn spend(&mut self, since: Instant) {
+        self.spent = self.spent.saturating_add(since.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_state() {
+        let budget = RetryBudget { max_retries: 4, max_time: None, per_item: 2, backoff: Duration::from_millis(10) };
+        let mut state = RetryState::new(budget);
+        assert_eq!(state.take(1, None), Some(Duration::from_millis(10)));
+        assert_eq!(state.take(2, None), Some(Duration::from_millis(20)));
+        assert_eq!(state.take(3, None), None); // per item limit
+        assert_eq!(state.take(1, None), Some(Duration::from_millis(10)));
+        assert_eq!(state.take(1, None), Some(Duration::from_millis(10)));
+        assert_eq!(state.take(1, None), None); // batch limit
+        let budget = RetryBudget { max_retries: 100, max_time: Some(Duration::from_millis(25)), per_item: 5, backoff: Duration::from_millis(10) };
+        let mut state = RetryState::new(budget);
+        let start = Instant::now() - Duration::from_millis(20);
+        assert!(state.take(1, None).is_some());
+        state.spend(start);
+        assert_eq!(state.take(1, None), None); // 20ms spent, 10ms more would exceed 25ms
+        assert_eq!(RetryState::new(RetryBudget::NONE).take(1, None), None);
+        // the server's delay if it's longer than the backoff
+        let mut state = RetryState::new(RetryBudget::default());
+        assert_eq!(state.take(1, Some(Duration::from_secs(5))), Some(Duration::from_secs(5)));
+        assert_eq!(state.take(3, Some(Duration::from_secs(2))), Some(Duration::from_secs(4)));
+    }
+}
@@ -0,0 +1,155 @@
+//! Helpers for barcode scanner input
+
+use crate::{Ean, InvalidEan};
+
+/// The digit a character stands for, also for typical keyboard layout mixups
+fn digit(c: char) -> Option<char> {
+    match c {
+        '0'..='9' => Some(c),
+        // fullwidth digits from an active IME
+        '０'..='９' => char::from_u32(u32::from(c) - u32::from('０') + u32::from('0')),
+        // scanner configured for US layout, typing on a French AZERTY keyboard (number row without shift)
+        '&' => Some('1'),
+        'é' => Some('2'),
+        '"' => Some('3'),
+        '\'' => Some('4'),
+        '(' => Some('5'),
+        '§' => Some('6'), // Belgian AZERTY, French has '-' which is treated as separator
+        'è' => Some('7'),
+        '_' | '!' => Some('8'), // French / Belgian AZERTY
+        'ç' => Some('9'),
+        'à' => Some('0'),
+        _ => None,
+    }
+}
+
+/// Clean up a scanned code and parse it as [`Ean`]
+///
+/// Removes whitespace and line endings, a leading AIM symbology identifier (eg. `]E0`),
+/// separators like '-' and an EAN-2 / EAN-5 add-on, and maps characters of a mismatched
+/// keyboard layout (AZERTY number row, fullwidth digits) back to digits.
+pub fn clean(scan: &str) -> Result<Ean, InvalidEan> {
+    let scan = scan.trim();
+    // symbology identifier: ']', a letter for the symbology and a modifier character
+    let scan = match scan.strip_prefix(']') {
+        Some(rest) => rest.char_indices().nth(2).map_or("", |(i, _)| &rest[i..]),
+        None => scan,
+    };
+    let mut code = String::with_capacity(scan.len());
+    for c in scan.chars() {
+        if c.is_whitespace() || c == '-' || c == '\u{0}' {
+            continue;
+        }
+        code.push(digit(c).ok_or(InvalidEan::NotADigit(c))?);
+    }
+    Ean::split_addon(&code).map(|(ean, _addon)| ean)
+}
+
+/// Assembles codes from keyboard-wedge scanner input, where the scanner "types" the code
+/// one character at a time and ends it with Enter (or Tab)
+///
+/// Each complete code is cleaned up with [`clean`]. Input that grows beyond any barcode
+/// length is dropped until the next Enter and reported as [`InvalidEan::Length`].
+#[derive(Clone, Debug, Default)]
+pub struct WedgeParser {
+    buffer: String,
+    len: usize,
+}
+
+impl WedgeParser {
+    // longest input clean() can accept: symbology identifier, GTIN-14 with EAN-5 add-on and separators
+    const MAX_LEN: usize = 32;
+
+    /// A parser waiting for the first character of a code
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one character, returns the code when this character completes it
+    pub fn push(&mut self, c: char) -> Option<Result<Ean, InvalidEan>> {
+        match c {
+            '\r' | '\n' | '\t' => {
+                let len = std::mem::take(&mut self.len);
+                let code = std::mem::take(&mut self.buffer);
+                match len {
+                    0 => None, // eg. the '\n' of "\r\n"
+                    len if len > Self::MAX_LEN => Some(Err(InvalidEan::Length(len))),
+                    _ => Some(clean(&code)),
+                }
+            }
+            '\u{1b}' => {
+                // Escape: discard the current input
+                self.reset();
+                None
+            }
+            c => {
+                self.len += 1;
+                if self.len <= Self::MAX_LEN {
+                    self.buffer.push(c);
+                }
+                None
+            }
+        }
+    }
+
+    /// Feed several characters, returns all codes they complete
+    pub fn push_str(&mut self, input: &str) -> Vec<Result<Ean, InvalidEan>> {
+        input.chars().filter_map(|c| self.push(c)).collect()
+    }
+
+    /// Discard the input of an incomplete code
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean() {
+        let expected = Ean::parse("5099750442227").unwrap();
+        for scan in [
+            "5099750442227",
+            "  5099750442227\r\n",
+            "]E05099750442227",
+            "]E0 5099750442227\r",
+            "5-099750-442227",
+            "(àççè(à''éééè", // AZERTY
+            "５０９９７５０４４２２２７",
+            "]E3509975044222701", // with EAN-2 add-on
+        ] {
+            assert_eq!(clean(scan), Ok(expected), "{:?}", scan);
+        }
+        assert_eq!(clean("]E496385074").unwrap().to_string(), "96385074");
+        assert_eq!(clean("5099750442227x"), Err(InvalidEan::NotADigit('x')));
+        assert_eq!(clean("5099750442228"), Err(InvalidEan::CheckDigit { expected: 7, found: 8 }));
+        assert_eq!(clean(""), Err(InvalidEan::Length(0)));
+        assert_eq!(clean("]E"), Err(InvalidEan::Length(0)));
+    }
+
+    #[test]
+    fn test_wedge_parser() {
+        let mut parser = WedgeParser::new();
+        for c in "509975044222".chars() {
+            assert_eq!(parser.push(c), None);
+        }
+        assert_eq!(parser.push('7'), None);
+        assert_eq!(parser.push('\r'), Some(Ok(Ean::parse("5099750442227").unwrap())));
+        assert_eq!(parser.push('\n'), None);
+        let codes = parser.push_str("]E496385074\r\n5099750442228\n\n123\u{1b}036000291452\t");
+        assert_eq!(codes, vec![
+            Ok(Ean::parse("96385074").unwrap()),
+            Err(InvalidEan::CheckDigit { expected: 7, found: 8 }),
+            Ok(Ean::parse("036000291452").unwrap()),
+        ]);
+        let codes = parser.push_str(&"1".repeat(1000));
+        assert!(codes.is_empty());
+        assert_eq!(parser.push('\n'), Some(Err(InvalidEan::Length(1000))));
+        // incomplete input is kept until the next Enter
+        parser.push_str("5099750");
+        assert_eq!(parser.push_str("442227\n"), vec![Ok(Ean::parse("5099750442227").unwrap())]);
+    }
+}
@@ -0,0 +1,226 @@
+//! Client-side refinements of the keyword search: exact phrases and boolean queries
+
+use crate::{EANSearchError, ErrorKind};
+
+/// A keyword query with AND / OR / NOT, see [`EANSearch::query_search`](crate::EANSearch::query_search)
+///
+/// The API only supports keywords that must all match, so a query is run as one API search
+/// per alternative (in disjunctive normal form) and the results are checked against the whole
+/// query, which is where NOT and phrases are applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    /// A keyword, matched as a whole word in the name (case insensitive);
+    /// with several words, each of them has to be in the name
+    Word(String),
+    /// Words that must occur next to each other, see [`contains_phrase`]
+    Phrase(String),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+/// A keyword or phrase, negated or not
+type Literal<'a> = (&'a Query, bool);
+
+/// The most alternatives a query may have, as each one costs an API call
+const MAX_ALTERNATIVES: usize = 16;
+
+impl Query {
+    /// A single keyword
+    pub fn word(word: &str) -> Query {
+        Query::Word(word.to_string())
+    }
+
+    /// An exact phrase
+    pub fn phrase(phrase: &str) -> Query {
+        Query::Phrase(phrase.to_string())
+    }
+
+    /// Both this and the other query must match
+    pub fn and(self, other: Query) -> Query {
+        match self {
+            Query::And(mut queries) => {
+                queries.push(other);
+                Query::And(queries)
+            }
+            query => Query::And(vec![query, other]),
+        }
+    }
+
+    /// This or the other query must match
+    pub fn or(self, other: Query) -> Query {
+        match self {
+            Query::Or(mut queries) => {
+                queries.push(other);
+                Query::Or(queries)
+            }
+            query => Query::Or(vec![query, other]),
+        }
+    }
+
+    /// This query must match, the other one must not
+    pub fn and_not(self, other: Query) -> Query {
+        self.and(Query::Not(Box::new(other)))
+    }
+
+    /// Whether a product name matches the query
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Query::Word(word) => {
+                let name = words(name);
+                words(word).iter().all(|word| name.contains(word))
+            }
+            Query::Phrase(phrase) => contains_phrase(name, phrase),
+            Query::And(queries) => queries.iter().all(|q| q.matches(name)),
+            Query::Or(queries) => queries.iter().any(|q| q.matches(name)),
+            Query::Not(query) => !query.matches(name),
+        }
+    }
+
+    /// The keyword searches to send to the API, one for each alternative of the query
+    ///
+    /// Fails if an alternative has no keyword that must match (eg. a query with only NOT),
+    /// as the API can't search for that, or if the query has more than 16 alternatives.
+    pub fn api_queries(&self) -> Result<Vec<String>, EANSearchError> {
+        let mut queries : Vec<String> = Vec::new();
+        for conjunction in self.dnf(false)? {
+            let words : Vec<&str> = conjunction.iter()
+                .filter(|(_, negated)| !negated)
+                .filter_map(|(literal, _)| match literal {
+                    Query::Word(words) | Query::Phrase(words) => Some(words.split_whitespace()),
+                    _ => None,
+                })
+                .flatten()
+                .collect();
+            if words.is_empty() {
+                return Err(ErrorKind::InvalidArgument(String::from("every alternative of a query needs a keyword that must match")).into());
+            }
+            let query = words.join(" ");
+            if !queries.contains(&query) {
+                queries.push(query);
+            }
+        }
+        Ok(queries)
+    }
+
+    /// The query as alternatives of conjunctions of keywords and phrases (NOT pushed down to them),
+    /// without duplicates and with at most [`MAX_ALTERNATIVES`]
+    fn dnf(&self, negated: bool) -> Result<Vec<Vec<Literal<'_>>>, ErrorKind> {
+        let mut alternatives = Vec::new();
+        match (self, negated) {
+            (Query::Word(_) | Query::Phrase(_), _) => alternatives.push(vec![(self, negated)]),
+            (Query::Not(query), _) => return query.dnf(!negated),
+            (Query::And(queries), false) | (Query::Or(queries), true) => {
+                alternatives.push(Vec::new());
+                for query in queries {
+                    let right = query.dnf(negated)?;
+                    let mut product = Vec::new();
+                    for left in &alternatives {
+                        for right in &right {
+                            let mut conjunction : Vec<Literal> = left.clone();
+                            for literal in right {
+                                if !conjunction.contains(literal) {
+                                    conjunction.push(*literal);
+                                }
+                            }
+                            push_alternative(&mut product, conjunction)?;
+                        }
+                    }
+                    alternatives = product;
+                }
+            }
+            (Query::Or(queries), false) | (Query::And(queries), true) => {
+                for query in queries {
+                    for conjunction in query.dnf(negated)? {
+                        push_alternative(&mut alternatives, conjunction)?;
+                    }
+                }
+            }
+        }
+        Ok(alternatives)
+    }
+}
+
+/// Add a conjunction unless it's already there (in any order), failing above [`MAX_ALTERNATIVES`]
+fn push_alternative<'a>(alternatives: &mut Vec<Vec<Literal<'a>>>, conjunction: Vec<Literal<'a>>) -> Result<(), ErrorKind> {
+    let same = |other: &Vec<Literal>| other.len() == conjunction.len() && other.iter().all(|literal| conjunction.contains(literal));
+    if !alternatives.iter().any(same) {
+        if alternatives.len() == MAX_ALTERNATIVES {
+            let msg = format!("the query has more than {} alternatives (one API call each)", MAX_ALTERNATIVES);
+            return Err(ErrorKind::InvalidArgument(msg));
+        }
+        alternatives.push(conjunction);
+    }
+    Ok(())
+}
+
+/// The lowercase words of a text, split at anything that isn't a letter or digit
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Whether the words of the phrase occur in this order, next to each other, in the text
+///
+/// Matching is case insensitive and ignores punctuation, so "galaxy s23 ultra" is in
+/// "Samsung Galaxy S23 Ultra, 256GB" but not in "Galaxy S23 / S23 Ultra Case".
+pub fn contains_phrase(text: &str, phrase: &str) -> bool {
+    let phrase = words(phrase);
+    if phrase.is_empty() {
+        return true;
+    }
+    words(text).windows(phrase.len()).any(|window| window == phrase.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_phrase() {
+        assert!(contains_phrase("Samsung Galaxy S23 Ultra, 256GB", "galaxy s23 ultra"));
+        assert!(contains_phrase("Samsung Galaxy S23 Ultra", "Galaxy  S23-Ultra"));
+        assert!(!contains_phrase("Galaxy S23 / S23 Ultra Case", "Galaxy S23 Ultra"));
+        assert!(!contains_phrase("Galaxy S23 Ultrawide", "Galaxy S23 Ultra"));
+        assert!(contains_phrase("Bananaboat Song", ""));
+        assert!(!contains_phrase("", "bananaboat"));
+    }
+
+    #[test]
+    fn test_query() {
+        let query = Query::word("thriller").and(Query::word("jackson").or(Query::phrase("michael jackson"))).and_not(Query::word("live"));
+        assert_eq!(query.api_queries().unwrap(), vec!["thriller jackson", "thriller michael jackson"]);
+        assert!(query.matches("Michael Jackson: Thriller"));
+        assert!(!query.matches("Michael Jackson: Thriller Live"));
+        assert!(!query.matches("Thriller"));
+        // NOT (a AND b) = NOT a OR NOT b
+        let query = Query::word("bananaboat").and(Query::Not(Box::new(Query::word("song").and(Query::word("remix")))));
+        assert_eq!(query.api_queries().unwrap(), vec!["bananaboat"]);
+        assert!(query.matches("Bananaboat Song"));
+        assert!(!query.matches("Bananaboat Song Remix"));
+        assert!(Query::Not(Box::new(Query::word("live"))).api_queries().is_err());
+        assert!(Query::word("a").or(Query::Not(Box::new(Query::word("b")))).api_queries().is_err());
+        // keywords match whole words
+        assert!(!Query::word("live").matches("Express Delivery"));
+        assert!(Query::word("live").matches("Thriller (Live)"));
+        assert!(Query::word("thriller live").matches("Live: Thriller"));
+    }
+
+    #[test]
+    fn test_query_alternatives() {
+        // duplicates are only searched once
+        let query = Query::word("a").or(Query::word("a")).and(Query::word("b").or(Query::word("b")));
+        assert_eq!(query.api_queries().unwrap(), vec!["a b"]);
+        let query = Query::word("a").and(Query::word("b")).or(Query::word("b").and(Query::word("a")));
+        assert_eq!(query.dnf(false).unwrap().len(), 1);
+        // (a1 OR b1) AND (a2 OR b2) AND ... has 2^n alternatives
+        let pair = |i: usize| Query::word(&format!("a{}", i)).or(Query::word(&format!("b{}", i)));
+        let query = (1..4).fold(pair(0), |query, i| query.and(pair(i)));
+        assert_eq!(query.api_queries().unwrap().len(), 16);
+        let query = (1..30).fold(pair(0), |query, i| query.and(pair(i)));
+        let e = query.api_queries().unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::InvalidArgument(_)));
+    }
+}
@@ -0,0 +1,168 @@
+//! tower services for the API operations (enabled with the `tower` feature)
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tower_service::Service;
+
+use crate::transport::{AsyncTransport, DefaultAsyncTransport};
+use crate::{AsyncEANSearch, EANSearchError, Operation, PageRequest, Product, Response};
+
+/// The future returned by the services
+#[cfg(not(target_arch = "wasm32"))]
+pub type ServiceFuture<R> = Pin<Box<dyn Future<Output = Result<R, EANSearchError>> + Send>>;
+/// The future returned by the services
+#[cfg(target_arch = "wasm32")]
+pub type ServiceFuture<R> = Pin<Box<dyn Future<Output = Result<R, EANSearchError>>>>;
+
+/// A [`tower_service::Service`] running any [`Operation`] with an [`AsyncEANSearch`] client
+///
+/// Clones share the client, so the service can be wrapped in tower layers (retry, rate limit, tracing, ...).
+/// It is always ready: use a rate limit or concurrency layer to hold requests back.
+pub struct EANSearchService<T: AsyncTransport = DefaultAsyncTransport> {
+    client: Arc<AsyncEANSearch<T>>,
+}
+
+impl<T: AsyncTransport> EANSearchService<T> {
+    /// A service running the operations with a client
+    pub fn new(client: AsyncEANSearch<T>) -> Self {
+        Self { client: Arc::new(client) }
+    }
+
+    /// A service looking up EAN barcodes (in the default language of the client)
+    pub fn barcode_lookup(&self) -> BarcodeLookupService<T> {
+        BarcodeLookupService { client: self.client.clone() }
+    }
+
+    /// A service searching products by keywords (in the default language of the client)
+    pub fn product_search(&self) -> ProductSearchService<T> {
+        ProductSearchService { client: self.client.clone() }
+    }
+}
+
+impl<T: AsyncTransport> Clone for EANSearchService<T> {
+    fn clone(&self) -> Self {
+        Self { client: self.client.clone() }
+    }
+}
+
+impl<T: AsyncTransport + 'static> Service<Operation> for EANSearchService<T> {
+    type Response = Response;
+    type Error = EANSearchError;
+    type Future = ServiceFuture<Response>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), EANSearchError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, op: Operation) -> Self::Future {
+        let client = self.client.clone();
+        Box::pin(async move { client.execute(&op).await })
+    }
+}
+
+/// A [`tower_service::Service`] looking up an EAN barcode, see [`EANSearchService::barcode_lookup`]
+pub struct BarcodeLookupService<T: AsyncTransport = DefaultAsyncTransport> {
+    client: Arc<AsyncEANSearch<T>>,
+}
+
+impl<T: AsyncTransport> Clone for BarcodeLookupService<T> {
+    fn clone(&self) -> Self {
+        Self { client: self.client.clone() }
+    }
+}
+
+impl<T: AsyncTransport + 'static> Service<u64> for BarcodeLookupService<T> {
+    type Response = Option<Product>;
+    type Error = EANSearchError;
+    type Future = ServiceFuture<Option<Product>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), EANSearchError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, ean: u64) -> Self::Future {
+        let client = self.client.clone();
+        Box::pin(async move { client.barcode_lookup(ean, None).await })
+    }
+}
+
+/// A [`tower_service::Service`] searching products by keywords, see [`EANSearchService::product_search`]
+///
+/// It takes the keywords for the first page of results, or the keywords and a page.
+pub struct ProductSearchService<T: AsyncTransport = DefaultAsyncTransport> {
+    client: Arc<AsyncEANSearch<T>>,
+}
+
+impl<T: AsyncTransport> Clone for ProductSearchService<T> {
+    fn clone(&self) -> Self {
+        Self { client: self.client.clone() }
+    }
+}
+
+impl<T: AsyncTransport + 'static> Service<(String, PageRequest)> for ProductSearchService<T> {
+    type Response = Vec<Product>;
+    type Error = EANSearchError;
+    type Future = ServiceFuture<Vec<Product>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), EANSearchError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, (name, page): (String, PageRequest)) -> Self::Future {
+        let client = self.client.clone();
+        Box::pin(async move { client.product_search(&name, None, page).await })
+    }
+}
+
+impl<T: AsyncTransport + 'static> Service<String> for ProductSearchService<T> {
+    type Response = Vec<Product>;
+    type Error = EANSearchError;
+    type Future = ServiceFuture<Vec<Product>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), EANSearchError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: String) -> Self::Future {
+        self.call((name, PageRequest::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, ErrorKind, HttpRequest, HttpResponse};
+
+    /// Answers every request with the same body
+    struct Canned(&'static str);
+
+    impl AsyncTransport for Canned {
+        async fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+            Ok(HttpResponse { status: 200, headers: Vec::new(), body: self.0.as_bytes().to_vec() })
+        }
+    }
+
+    async fn oneshot<S: Service<R>, R>(mut service: S, request: R) -> Result<S::Response, S::Error> {
+        std::future::poll_fn(|cx| service.poll_ready(cx)).await?;
+        service.call(request).await
+    }
+
+    #[tokio::test]
+    async fn test_service() {
+        let service = EANSearchService::new(AsyncEANSearch::with_transport("secret", Canned(fixtures::BARCODE_LOOKUP)));
+        let product = oneshot(service.barcode_lookup(), 5099750442227).await.unwrap().unwrap();
+        assert_eq!(product.name, "Michael Jackson: Thriller");
+        let response = oneshot(service.clone(), Operation::BarcodeLookup { ean: 5099750442227, language: None }).await.unwrap();
+        assert!(matches!(response, Response::Product(Some(_))));
+        // the futures can be spawned
+        let lookup = service.barcode_lookup().call(5099750442227);
+        assert!(tokio::spawn(lookup).await.unwrap().is_ok());
+
+        let service = EANSearchService::new(AsyncEANSearch::with_transport("secret", Canned(fixtures::PRODUCT_SEARCH)));
+        let products = oneshot(service.product_search(), String::from("Bananaman")).await.unwrap();
+        assert!(!products.is_empty());
+    }
+}
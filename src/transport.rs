@@ -0,0 +1,466 @@
+//! The HTTP layer of the clients
+
+use crate::{ErrorKind, HttpRequest, HttpResponse, REQUEST_ID_HEADER};
+
+/// Sends the requests of an [`EANSearch`](crate::EANSearch) client
+///
+/// Implement it to use another HTTP stack or a test double with
+/// [`EANSearch::with_transport`](crate::EANSearch::with_transport).
+/// The request has to be sent with the `request_id` in the [`REQUEST_ID_HEADER`] and all `headers` of the request.
+/// Report failures as [`ErrorKind::Transport`] (there's no need to handle HTTP error statuses,
+/// the client looks at the body).
+#[cfg(feature = "_client")]
+pub trait Transport: Send + Sync {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind>;
+}
+
+/// The transport [`EANSearch::new`](crate::EANSearch::new) uses: reqwest, or ureq if only the `ureq` feature is enabled
+#[cfg(feature = "blocking")]
+pub type DefaultTransport = ReqwestTransport;
+/// The transport [`EANSearch::new`](crate::EANSearch::new) uses: reqwest, or ureq if only the `ureq` feature is enabled
+#[cfg(all(feature = "ureq", not(feature = "blocking")))]
+pub type DefaultTransport = UreqTransport;
+
+/// The default transport with reqwest's blocking client
+#[cfg(feature = "blocking")]
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "blocking")]
+impl ReqwestTransport {
+    /// A transport with a configured reqwest client
+    pub fn new(client: reqwest::blocking::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Transport for ReqwestTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|_| ErrorKind::InvalidArgument(format!("invalid HTTP method {}", request.method)))?;
+        let mut builder = self.client.request(method, &request.url).header(REQUEST_ID_HEADER, &request.request_id);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let response = builder.send()?;
+        let status = response.status().as_u16();
+        let headers = response.headers().iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        Ok(HttpResponse { status, headers, body: response.bytes()?.to_vec() })
+    }
+}
+
+/// A lightweight transport with ureq (enabled with the `ureq` feature)
+#[cfg(feature = "ureq")]
+#[derive(Clone, Debug)]
+pub struct UreqTransport {
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "ureq")]
+impl UreqTransport {
+    /// A transport with a configured ureq agent (it has to return HTTP error statuses as responses,
+    /// see `http_status_as_error`)
+    pub fn new(agent: ureq::Agent) -> Self {
+        Self { agent }
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl Default for UreqTransport {
+    fn default() -> Self {
+        Self::new(ureq::Agent::config_builder().http_status_as_error(false).build().into())
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl Transport for UreqTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+        let failed = |e: ureq::Error| ErrorKind::Transport(Box::new(e));
+        let mut builder = match request.method {
+            "GET" => self.agent.get(&request.url),
+            method => return Err(ErrorKind::InvalidArgument(format!("unsupported HTTP method {}", method))),
+        };
+        builder = builder.header(REQUEST_ID_HEADER, &request.request_id);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(timeout) = request.timeout {
+            builder = builder.config().timeout_global(Some(timeout)).build();
+        }
+        let mut response = builder.call().map_err(failed)?;
+        let status = response.status().as_u16();
+        let headers = response.headers().iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response.body_mut().read_to_vec().map_err(failed)?;
+        Ok(HttpResponse { status, headers, body })
+    }
+}
+
+/// Sends the requests of an [`AsyncEANSearch`](crate::AsyncEANSearch) client, like a [`Transport`] for async code
+///
+/// Implement it to use another HTTP stack, eg. on another async runtime, with
+/// [`AsyncEANSearch::with_transport`](crate::AsyncEANSearch::with_transport).
+/// The futures have to be `Send`, except on wasm32 (where reqwest's futures aren't).
+#[cfg(feature = "_async")]
+pub trait AsyncTransport: Send + Sync {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn send(&self, request: &HttpRequest) -> impl std::future::Future<Output = Result<HttpResponse, ErrorKind>> + Send;
+    #[cfg(target_arch = "wasm32")]
+    fn send(&self, request: &HttpRequest) -> impl std::future::Future<Output = Result<HttpResponse, ErrorKind>>;
+}
+
+/// The transport [`AsyncEANSearch::new`](crate::AsyncEANSearch::new) uses: reqwest, or ureq on a thread pool
+/// if only the `async-ureq` feature is enabled
+#[cfg(feature = "async")]
+pub type DefaultAsyncTransport = AsyncReqwestTransport;
+/// The transport [`AsyncEANSearch::new`](crate::AsyncEANSearch::new) uses: reqwest, or ureq on a thread pool
+/// if only the `async-ureq` feature is enabled
+#[cfg(all(feature = "async-ureq", not(feature = "async")))]
+pub type DefaultAsyncTransport = UnblockTransport;
+
+/// The default async transport with reqwest's async client (it needs a tokio runtime, except on wasm32)
+#[cfg(feature = "async")]
+#[derive(Clone, Debug, Default)]
+pub struct AsyncReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "async")]
+impl AsyncReqwestTransport {
+    /// A transport with a configured reqwest client
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for AsyncReqwestTransport {
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|_| ErrorKind::InvalidArgument(format!("invalid HTTP method {}", request.method)))?;
+        let mut builder = self.client.request(method, &request.url).header(REQUEST_ID_HEADER, &request.request_id);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        // fetch has no timeouts
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let response = builder.send().await?;
+        let status = response.status().as_u16();
+        let headers = response.headers().iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        Ok(HttpResponse { status, headers, body: response.bytes().await?.to_vec() })
+    }
+}
+
+/// An async transport with a [`reqwest_middleware::ClientWithMiddleware`] (enabled with the `middleware` feature),
+/// so retry, tracing or caching middleware applies to every API call
+///
+/// Errors of the middleware are reported as [`ErrorKind::Transport`], reqwest's errors as [`ErrorKind::Http`].
+#[cfg(feature = "middleware")]
+#[derive(Clone, Debug)]
+pub struct MiddlewareTransport {
+    client: reqwest_middleware::ClientWithMiddleware,
+}
+
+#[cfg(feature = "middleware")]
+impl MiddlewareTransport {
+    /// A transport with a client and its middleware
+    pub fn new(client: reqwest_middleware::ClientWithMiddleware) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "middleware")]
+impl AsyncTransport for MiddlewareTransport {
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+        let failed = |e: reqwest_middleware::Error| match e {
+            reqwest_middleware::Error::Reqwest(e) => ErrorKind::from(e),
+            reqwest_middleware::Error::Middleware(e) => ErrorKind::Transport(e.into()),
+        };
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|_| ErrorKind::InvalidArgument(format!("invalid HTTP method {}", request.method)))?;
+        let mut builder = self.client.request(method, &request.url).header(REQUEST_ID_HEADER, &request.request_id);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        // fetch has no timeouts
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let response = builder.send().await.map_err(failed)?;
+        let status = response.status().as_u16();
+        let headers = response.headers().iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        Ok(HttpResponse { status, headers, body: response.bytes().await?.to_vec() })
+    }
+}
+
+/// An async transport that runs a blocking [`Transport`] on the thread pool of the `blocking` crate
+/// (enabled with the `async-ureq` feature)
+///
+/// It doesn't depend on an async runtime, so it works with async-std, smol or any other executor.
+#[cfg(feature = "async-ureq")]
+#[derive(Debug)]
+pub struct UnblockTransport<T = UreqTransport> {
+    transport: std::sync::Arc<T>,
+}
+
+#[cfg(feature = "async-ureq")]
+impl<T> UnblockTransport<T> {
+    /// An async transport sending the requests with a blocking transport
+    pub fn new(transport: T) -> Self {
+        Self { transport: std::sync::Arc::new(transport) }
+    }
+}
+
+#[cfg(feature = "async-ureq")]
+impl<T: Default> Default for UnblockTransport<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(feature = "async-ureq")]
+impl<T> Clone for UnblockTransport<T> {
+    fn clone(&self) -> Self {
+        Self { transport: self.transport.clone() }
+    }
+}
+
+#[cfg(feature = "async-ureq")]
+impl<T: Transport + 'static> AsyncTransport for UnblockTransport<T> {
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+        let transport = self.transport.clone();
+        let request = request.clone();
+        blocking::unblock(move || transport.send(&request)).await
+    }
+}
+
+/// An async transport with a hyper client (enabled with the `hyper` feature),
+/// eg. to tune the connection pool with [`hyper::client::Builder`]
+#[cfg(feature = "hyper")]
+#[derive(Clone, Debug)]
+pub struct HyperTransport<C = hyper_tls::HttpsConnector<hyper::client::HttpConnector>> {
+    client: hyper::Client<C>,
+}
+
+#[cfg(feature = "hyper")]
+impl<C> HyperTransport<C> {
+    /// A transport with a configured hyper client (it needs a TLS connector for the default API endpoint)
+    pub fn new(client: hyper::Client<C>) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "hyper")]
+impl Default for HyperTransport {
+    fn default() -> Self {
+        Self::new(hyper::Client::builder().build(hyper_tls::HttpsConnector::new()))
+    }
+}
+
+#[cfg(feature = "hyper")]
+impl<C> AsyncTransport for HyperTransport<C>
+where
+    C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+{
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+        let mut builder = hyper::Request::builder()
+            .method(request.method)
+            .uri(&request.url)
+            .header(REQUEST_ID_HEADER, &request.request_id);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        let hyper_request = builder.body(hyper::Body::empty()).map_err(|e| ErrorKind::Transport(Box::new(e)))?;
+        let failed = |e: hyper::Error| ErrorKind::Transport(Box::new(e));
+        let exchange = async {
+            let response = self.client.request(hyper_request).await.map_err(failed)?;
+            let status = response.status().as_u16();
+            let headers = response.headers().iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect();
+            let body = hyper::body::to_bytes(response.into_body()).await.map_err(failed)?;
+            Ok(HttpResponse { status, headers, body: body.to_vec() })
+        };
+        match request.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, exchange).await.map_err(|e| ErrorKind::Transport(Box::new(e)))?,
+            None => exchange.await,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "_client"))]
+mod tests {
+    use super::*;
+    use crate::{fixtures, EANSearch, Operation, QuotaGuard};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// Answers every request with the same response and remembers the requests
+    struct Canned {
+        response: HttpResponse,
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl Canned {
+        fn new(status: u16, header: (&str, &str), body: &str) -> Self {
+            let headers = vec![(header.0.to_string(), header.1.to_string())];
+            Self { response: HttpResponse { status, headers, body: body.as_bytes().to_vec() }, requests: Mutex::new(Vec::new()) }
+        }
+    }
+
+    impl Transport for Canned {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+            self.requests.lock().unwrap().push(request.clone());
+            Ok(self.response.clone())
+        }
+    }
+
+    #[test]
+    fn test_transport() {
+        let transport = Canned::new(200, ("x-credits-remaining", "50"), fixtures::BARCODE_LOOKUP);
+        let eansearch = EANSearch::with_transport("secret", transport).with_quota_guard(QuotaGuard::new(100, 10));
+        let product = eansearch.barcode_lookup(5099750442227, Some(1)).unwrap().unwrap();
+        assert_eq!(product.name, "Michael Jackson: Thriller");
+        assert_eq!(eansearch.quota_guard().unwrap().credits_remaining(), Some(50));
+        let requests = eansearch.transport().requests.lock().unwrap();
+        assert_eq!(requests[0].url, "https://api.ean-search.org/api?format=json&token=secret&op=barcode-lookup&ean=5099750442227&language=1");
+        assert_eq!(requests[0].request_id.len(), 16);
+    }
+
+    #[test]
+    fn test_iter_last_page() {
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("x-credits-remaining", "50"), fixtures::PRODUCT_SEARCH));
+        let op = Operation::ProductSearch { name: String::from("bananaboat"), language: None, page: Default::default() };
+        assert_eq!(eansearch.iter_products(op).count(), 2);
+        // moreproducts is false, so the empty next page isn't requested
+        assert_eq!(eansearch.transport().requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_download_last_page() {
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("x-credits-remaining", "50"), fixtures::PRODUCT_SEARCH));
+        let mut download = eansearch.download_category_pages(45, None, crate::DownloadOptions::default());
+        assert_eq!(download.next().unwrap().unwrap().products.len(), 2);
+        assert_eq!(download.checkpoint(), None);
+        assert!(download.next().is_none());
+        assert_eq!(eansearch.transport().requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_usage_failed_request() {
+        struct Unreachable;
+
+        impl Transport for Unreachable {
+            fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, ErrorKind> {
+                Err(ErrorKind::Transport("connection refused".into()))
+            }
+        }
+
+        let eansearch = EANSearch::with_transport("secret", Unreachable);
+        assert!(eansearch.barcode_lookup(5099750442227, None).is_err());
+        assert_eq!((eansearch.usage().requests, eansearch.usage().credits), (0, 0));
+
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("x-credits-remaining", "50"), fixtures::BARCODE_LOOKUP));
+        eansearch.barcode_lookup(5099750442227, None).unwrap();
+        assert_eq!((eansearch.usage().requests, eansearch.usage().credits), (1, 1));
+    }
+
+    #[test]
+    fn test_credits_remaining() {
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("x-credits-remaining", "50"), fixtures::BARCODE_LOOKUP));
+        eansearch.barcode_lookup(5099750442227, None).unwrap();
+        assert_eq!(eansearch.credits_remaining().unwrap(), 50);
+        assert_eq!(eansearch.transport().requests.lock().unwrap().len(), 1);
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("x-credits-remaining", "50"), fixtures::ACCOUNT_STATUS));
+        assert_eq!(eansearch.credits_remaining().unwrap(), 90);
+        assert_eq!(eansearch.refresh_credits().unwrap(), 90);
+        assert_eq!(eansearch.transport().requests.lock().unwrap().len(), 2);
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("x-credits-remaining", "50"), fixtures::MAINTENANCE_PAGE));
+        assert!(eansearch.credits_remaining().is_err());
+    }
+
+    #[test]
+    fn test_quota_guard_recovers() {
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("content-type", "application/json"), fixtures::ACCOUNT_STATUS))
+            .with_quota_guard(QuotaGuard::new(50, 10));
+        eansearch.quota_guard().unwrap().observe(5);
+        let e = eansearch.barcode_lookup(5099750442227, None).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::QuotaGuardTripped { credits_remaining: 5, threshold: 10 }));
+        // the account status is free and shows the renewed credits
+        assert_eq!(eansearch.refresh_credits().unwrap(), 90);
+        assert!(!eansearch.quota_guard().unwrap().is_tripped());
+        let e = eansearch.barcode_lookup(5099750442227, None).unwrap_err();
+        assert!(!matches!(e.kind(), ErrorKind::QuotaGuardTripped { .. }));
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let eansearch = EANSearch::with_transport("secret", Canned::new(429, ("Retry-After", "30"), fixtures::RATE_LIMITED));
+        let e = eansearch.barcode_lookup(5099750442227, None).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::RateLimited { retry_after: Some(_) }));
+        assert_eq!(e.retry_after(), Some(Duration::from_secs(30)));
+        assert!(e.is_retryable());
+        let context = e.context().unwrap();
+        assert_eq!(context.status, Some(429));
+        assert_eq!(context.body.as_deref(), Some(fixtures::RATE_LIMITED));
+        let budget = crate::RetryBudget { max_time: Some(Duration::from_secs(10)), ..Default::default() };
+        let results = eansearch.lookup_many_with_retries(&[5099750442227], None, budget);
+        // no retry, waiting 30s would exceed the budget
+        assert!(results[0].1.is_err());
+        assert_eq!(eansearch.transport().requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_token_errors() {
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("x-credits-remaining", "100"), fixtures::INVALID_TOKEN));
+        let e = eansearch.account_status().unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::InvalidToken(_)));
+        assert_eq!(e.api_message(), Some("Invalid token"));
+        let body = r#"[{"error":"Request limit reached"}]"#;
+        let eansearch = EANSearch::with_transport("secret", Canned::new(200, ("x-credits-remaining", "0"), body));
+        let e = eansearch.barcode_lookup(5099750442227, None).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::QuotaExhausted(_)));
+        assert_eq!(e.api_error_kind(), Some(crate::ApiErrorKind::QuotaExhausted));
+        assert!(!e.is_retryable());
+    }
+
+    #[test]
+    fn test_timeout() {
+        // accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let eansearch = EANSearch::builder("secret").base_url(&url).timeout(Duration::from_millis(100)).build().unwrap();
+        let start = Instant::now();
+        let e = eansearch.account_status().unwrap_err();
+        assert!(e.is_retryable());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(feature = "ureq")]
+    #[test]
+    fn test_ureq() {
+        // nothing listens on the discard port
+        let eansearch = EANSearch::with_transport("secret", UreqTransport::default())
+            .with_endpoint(crate::Endpoint::with_url("http://127.0.0.1:9/api", "secret"));
+        let e = eansearch.account_status().unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Transport(_)));
+        assert!(e.is_retryable());
+    }
+}
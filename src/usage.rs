@@ -0,0 +1,113 @@
+//! Usage statistics of a client session and forecasts of the remaining credits
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// The requests a client made since it was created, see [`EANSearch::usage`](crate::EANSearch::usage)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionUsage {
+    /// Requests the API answered (requests that failed before a response don't count)
+    pub requests: u64,
+    /// Credits used by these requests, see [`Operation::credit_cost`](crate::Operation::credit_cost)
+    pub credits: u64,
+    /// Time since the client was created
+    pub elapsed: Duration,
+}
+
+/// Counts the requests of a client and remembers the latest remaining credits, shared between threads
+pub(crate) struct UsageCounter {
+    started: Instant,
+    requests: AtomicU64,
+    credits: AtomicU64,
+    // UNKNOWN until a response told
+    remaining: AtomicU64,
+}
+
+const UNKNOWN: u64 = u64::MAX;
+
+impl UsageCounter {
+    pub(crate) fn new() -> Self {
+        Self { started: Instant::now(), requests: AtomicU64::new(0), credits: AtomicU64::new(0), remaining: AtomicU64::new(UNKNOWN) }
+    }
+
+    pub(crate) fn observe_remaining(&self, credits: u32) {
+        self.remaining.store(u64::from(credits), Ordering::Relaxed);
+    }
+
+    /// The latest remaining credits reported by the API
+    pub(crate) fn remaining(&self) -> Option<u32> {
+        u32::try_from(self.remaining.load(Ordering::Relaxed)).ok()
+    }
+
+    pub(crate) fn record(&self, credits: u32) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.credits.fetch_add(u64::from(credits), Ordering::Relaxed);
+    }
+
+    pub(crate) fn usage(&self) -> SessionUsage {
+        SessionUsage {
+            requests: self.requests.load(Ordering::Relaxed),
+            credits: self.credits.load(Ordering::Relaxed),
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+/// How long the remaining credits last at the current rate, see [`EANSearch::forecast`](crate::EANSearch::forecast)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Forecast {
+    /// Credits remaining according to the account status
+    pub credits_remaining: u32,
+    /// Credits used per day in this session
+    pub credits_per_day: f64,
+    /// Days until the credits are used up (None if no credits were used yet)
+    pub days_left: Option<f64>,
+}
+
+impl Forecast {
+    /// Extrapolate the usage of a session
+    pub fn new(credits_remaining: u32, usage: &SessionUsage) -> Self {
+        const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+        let seconds = usage.elapsed.as_secs_f64();
+        let credits_per_day = if seconds > 0.0 { usage.credits as f64 / seconds * SECONDS_PER_DAY } else { 0.0 };
+        let days_left = (credits_per_day > 0.0).then(|| f64::from(credits_remaining) / credits_per_day);
+        Self { credits_remaining, credits_per_day, days_left }
+    }
+}
+
+/// Spread `interval` randomly by up to a tenth in either direction, so that many clients don't poll in lockstep
+pub(crate) fn jitter(interval: Duration) -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    // factor between 0.9 and 1.1 (intervals too long to spread stay as they are)
+    Duration::try_from_secs_f64(interval.as_secs_f64() * (0.9 + (random % 2001) as f64 / 10000.0)).unwrap_or(interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forecast() {
+        let usage = SessionUsage { requests: 60, credits: 50, elapsed: Duration::from_secs(3600) };
+        let forecast = Forecast::new(6000, &usage);
+        assert_eq!(forecast.credits_per_day, 1200.0);
+        assert_eq!(forecast.days_left, Some(5.0));
+        let idle = SessionUsage { requests: 1, credits: 0, elapsed: Duration::from_secs(3600) };
+        assert_eq!(Forecast::new(6000, &idle).days_left, None);
+        let counter = UsageCounter::new();
+        counter.record(1);
+        counter.record(0);
+        let usage = counter.usage();
+        assert_eq!((usage.requests, usage.credits), (2, 1));
+    }
+
+    #[test]
+    fn test_jitter() {
+        for _ in 0..100 {
+            let interval = jitter(Duration::from_secs(100));
+            assert!(interval >= Duration::from_secs(90) && interval <= Duration::from_secs(110));
+        }
+        assert!(jitter(Duration::MAX) >= Duration::MAX.mul_f64(0.9));
+    }
+}
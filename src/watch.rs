@@ -0,0 +1,117 @@
+//! Re-querying a set of EANs periodically and reporting changes to the products
+
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use crate::{EANSearch, EANSearchError, Product, Transport};
+
+/// A change to a watched product, or a failed lookup
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WatchEvent {
+    /// A product that wasn't found before is in the database now
+    Found(Product),
+    /// A product was renamed
+    NameChanged { ean: u64, old: String, new: String },
+    /// A product was moved to another category
+    CategoryChanged { ean: u64, old: (i32, String), new: (i32, String) },
+    /// A product that was found before isn't in the database anymore
+    NotFound { ean: u64, last: Product },
+    /// The lookup failed, the product is compared again in the next round
+    Failed { ean: u64, error: EANSearchError },
+}
+
+/// Looks up a set of EANs in rounds and reports the changes since the previous round
+///
+/// The first round only records the products (it reports failed lookups, but no changes).
+pub struct Watcher {
+    eans: Vec<u64>,
+    language: Option<i8>,
+    snapshot: HashMap<u64, Option<Product>>,
+}
+
+impl Watcher {
+    /// Watch these EANs, with product names in the language
+    pub fn new(eans: &[u64], language: Option<i8>) -> Self {
+        Self { eans: eans.to_vec(), language, snapshot: HashMap::new() }
+    }
+
+    /// The products from the last successful lookup of each EAN (None if it wasn't found)
+    pub fn snapshot(&self) -> &HashMap<u64, Option<Product>> {
+        &self.snapshot
+    }
+
+    /// Look up all EANs once and return the changes since the previous round
+    pub fn check<T: Transport>(&mut self, eansearch: &EANSearch<T>) -> Vec<WatchEvent> {
+        let mut events = Vec::new();
+        for (ean, result) in eansearch.lookup_many(&self.eans, self.language) {
+            match result {
+                Ok(product) => {
+                    if let Some(old) = self.snapshot.get(&ean) {
+                        events.extend(compare(ean, old, &product));
+                    }
+                    self.snapshot.insert(ean, product);
+                }
+                Err(error) => events.push(WatchEvent::Failed { ean, error }),
+            }
+        }
+        events
+    }
+
+    /// Check the EANs every `interval` and pass the events to the callback, until it returns `Break`
+    pub fn run<T, F>(&mut self, eansearch: &EANSearch<T>, interval: Duration, mut callback: F)
+    where
+        T: Transport,
+        F: FnMut(WatchEvent) -> ControlFlow<()>,
+    {
+        loop {
+            for event in self.check(eansearch) {
+                if callback(event).is_break() {
+                    return;
+                }
+            }
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+/// The changes between two lookups of the same EAN
+fn compare(ean: u64, old: &Option<Product>, new: &Option<Product>) -> Vec<WatchEvent> {
+    match (old, new) {
+        (None, Some(new)) => vec![WatchEvent::Found(new.clone())],
+        (Some(old), None) => vec![WatchEvent::NotFound { ean, last: old.clone() }],
+        (Some(old), Some(new)) => {
+            let mut events = Vec::new();
+            if old.name != new.name {
+                events.push(WatchEvent::NameChanged { ean, old: old.name.clone(), new: new.name.clone() });
+            }
+            if old.category_id != new.category_id || old.category_name != new.category_name {
+                let (old, new) = ((old.category_id, old.category_name.clone()), (new.category_id, new.category_name.clone()));
+                events.push(WatchEvent::CategoryChanged { ean, old, new });
+            }
+            events
+        }
+        (None, None) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_compare() {
+        let old = fixtures::product();
+        let mut new = old.clone();
+        assert!(compare(old.ean, &Some(old.clone()), &Some(new.clone())).is_empty());
+        assert!(compare(old.ean, &None, &None).is_empty());
+        assert!(matches!(&compare(old.ean, &None, &Some(new.clone()))[..], [WatchEvent::Found(p)] if p.ean == old.ean));
+        assert!(matches!(&compare(old.ean, &Some(old.clone()), &None)[..], [WatchEvent::NotFound { .. }]));
+        new.name = String::from("Thriller (Remastered)");
+        new.category_id = 46;
+        let events = compare(old.ean, &Some(old.clone()), &Some(new));
+        assert!(matches!(&events[..], [WatchEvent::NameChanged { new, .. }, WatchEvent::CategoryChanged { old: (45, _), new: (46, _), .. }] if new == "Thriller (Remastered)"));
+    }
+}